@@ -1,72 +1,676 @@
-use rand::{rngs::ThreadRng, seq::SliceRandom};
+//! Move search: minimax with alpha-beta pruning and a transposition table,
+//! and the strategies built on it.
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::IteratorRandom,
+    Rng,
+};
 
 use rayon::prelude::*;
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+pub mod mcts;
+pub mod solve;
+pub mod tt;
+
+use self::tt::{canonical_form, Bound, Entry, TranspositionTable};
+
+use crate::bitset::BitSet;
+
 use crate::game::{
-    apply_move, get_available_moves, is_game_over, is_game_won, Board, Piece, Position,
+    get_available_moves, is_game_over, is_game_won, no_more_moves, place_piece, runs, Board, Moves,
+    Piece, Position,
 };
 
-pub fn get_random_move(rng: &mut ThreadRng) -> Position {
-    *[
-        Position { x: 0, y: 0 },
-        Position { x: 1, y: 0 },
-        Position { x: 2, y: 0 },
-        Position { x: 0, y: 1 },
-        Position { x: 1, y: 1 },
-        Position { x: 2, y: 1 },
-        Position { x: 0, y: 2 },
-        Position { x: 1, y: 2 },
-        Position { x: 2, y: 2 },
-    ]
-    .choose(rng)
-    .unwrap()
-}
-
-pub fn get_random_valid_move(rng: &mut ThreadRng, board: &Board) -> Position {
-    *get_available_moves(board).choose(rng).unwrap()
-}
-
-pub fn pick_best_move_par(rng: &mut ThreadRng, board: &Board, piece: Piece) -> Position {
-    let available_moves = get_available_moves(board);
-    let (best_move, _best_score) = available_moves
+/// Score of a position won on the spot. Every ply it takes to get there
+/// costs one point, and heuristic scores always stay well below it.
+const WIN_SCORE: i32 = 1 << 28;
+
+/// Scores further than this from zero are wins or losses, not heuristics.
+const WIN_THRESHOLD: i32 = WIN_SCORE - BitSet::CAPACITY as i32;
+
+/// Boards with more cells than this only consider cells next to a piece.
+const FULL_WIDTH_CELLS: u32 = 16;
+
+/// Rough number of leaves a single search may visit before being cut short.
+const NODE_BUDGET: u64 = 2_000_000;
+
+/// Any empty cell, uniformly at random.
+pub fn get_random_valid_move(rng: &mut impl Rng, board: &Board) -> Position {
+    get_available_moves(board).choose(rng).unwrap()
+}
+
+/// How the computer plays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// Any empty cell.
+    Random,
+    /// Minimax search, optionally cut off `max_depth` plies past its own
+    /// move, that plays a random move instead with `blunder_chance`.
+    Minimax {
+        /// Plies searched past the computer's own move; `None` searches to the end.
+        max_depth: Option<u32>,
+        /// Probability of playing a random move instead of searching.
+        blunder_chance: f64,
+    },
+    /// Iterative-deepening minimax on the rayon pool that plays the best
+    /// move of the deepest search finished within `budget`.
+    Deepening {
+        /// Time allowed per move.
+        budget: Duration,
+    },
+    /// Monte Carlo tree search, optionally growing several trees side by
+    /// side on the rayon pool. It plays the most visited move, whatever the
+    /// selection.
+    Mcts {
+        /// How long each search may run.
+        budget: mcts::Budget,
+        /// How much the search favours rarely visited moves.
+        exploration: f64,
+        /// How many independent trees to grow; 1 grows a single tree on
+        /// the calling thread.
+        trees: u32,
+    },
+}
+
+impl Strategy {
+    /// Picks a move for `piece` on `board`.
+    pub fn pick_move(
+        &self,
+        rng: &mut impl Rng,
+        board: &Board,
+        piece: Piece,
+        selection: Selection,
+    ) -> Position {
+        match *self {
+            Strategy::Random => get_random_valid_move(rng, board),
+            Strategy::Minimax {
+                max_depth,
+                blunder_chance,
+            } => {
+                if rng.gen_bool(blunder_chance.clamp(0.0, 1.0)) {
+                    return get_random_valid_move(rng, board);
+                }
+                match max_depth {
+                    // the classic board is solved once and looked up after that
+                    None if board.is_classic() => {
+                        match solve::classic().score_moves(board, piece) {
+                            Some(scored) => select_move(rng, &scored, selection),
+                            None => pick_move_par(rng, board, piece, selection),
+                        }
+                    }
+                    None => pick_move_par(rng, board, piece, selection),
+                    Some(limit) => {
+                        // a private table, or entries from full-depth
+                        // searches would make the shallow search perfect
+                        let table = TranspositionTable::new(12);
+                        let limit = i32::try_from(limit).unwrap_or(i32::MAX);
+                        let max_depth = search_depth(board).min(limit);
+                        let scored = score_moves_with_par(
+                            board,
+                            candidate_moves(board).collect(),
+                            piece,
+                            max_depth,
+                            &table,
+                            &Clock::unlimited(),
+                        );
+                        select_move(rng, &scored, selection)
+                    }
+                }
+            }
+            Strategy::Deepening { budget } => {
+                pick_move_timed_par(rng, board, piece, budget, selection)
+            }
+            Strategy::Mcts {
+                budget,
+                exploration,
+                trees: 1,
+            } => mcts::pick_move(rng, board, piece, budget, exploration),
+            Strategy::Mcts {
+                budget,
+                exploration,
+                trees,
+            } => mcts::pick_move_par(rng, board, piece, budget, exploration, trees),
+        }
+    }
+}
+
+/// Preset strengths for the computer opponent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    /// Plays at random.
+    Easy,
+    /// Looks one reply ahead.
+    Medium,
+    /// Plays perfectly, except for the occasional random move.
+    Hard,
+    /// Never makes a mistake.
+    Perfect,
+}
+
+impl Difficulty {
+    /// The strategy that plays at this difficulty.
+    pub fn strategy(self) -> Strategy {
+        match self {
+            Difficulty::Easy => Strategy::Random,
+            // sees its own wins and the opponent's immediate threats, not forks
+            Difficulty::Medium => Strategy::Minimax {
+                max_depth: Some(1),
+                blunder_chance: 0.0,
+            },
+            Difficulty::Hard => Strategy::Minimax {
+                max_depth: None,
+                blunder_chance: 0.15,
+            },
+            Difficulty::Perfect => Strategy::Minimax {
+                max_depth: None,
+                blunder_chance: 0.0,
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "perfect" => Ok(Difficulty::Perfect),
+            _ => Err(format!("unknown difficulty: {}", s)),
+        }
+    }
+}
+
+/// How to choose among the scored moves at the root.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selection {
+    /// Uniformly at random among the moves with the best score.
+    Best,
+    /// Among the moves with the same outcome as the best one (all wins, all
+    /// draws or all losses), weighted by `exp((score - best) / temperature)`.
+    Softmax {
+        /// Higher values spread the choice more evenly. Must be finite and
+        /// greater than zero.
+        temperature: f64,
+    },
+}
+
+/// Tells a search when to stop: at a deadline, or as soon as any thread
+/// calls `cancel`. All the rayon workers of a search share one clock, so
+/// the first to notice the deadline stops the others too.
+#[derive(Debug)]
+pub struct Clock {
+    deadline: Option<Instant>,
+    stopped: AtomicBool,
+}
+
+impl Clock {
+    /// A clock that only stops when cancelled.
+    pub fn unlimited() -> Self {
+        Clock {
+            deadline: None,
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// A clock that stops `budget` from now.
+    pub fn with_budget(budget: Duration) -> Self {
+        Clock {
+            deadline: Instant::now().checked_add(budget),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// Stops every search running on this clock.
+    pub fn cancel(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the clock has been stopped, without looking at the time.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Whether a search should stop now.
+    fn expired(&self) -> bool {
+        if self.is_stopped() {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The only move worth searching, if there is just one.
+fn lone_move(board: &Board) -> Option<Position> {
+    let mut moves = candidate_moves(board);
+    match (moves.next(), moves.next()) {
+        (Some(pos), None) => Some(pos),
+        _ => None,
+    }
+}
+
+/// A best move for `piece`, searching the root moves on the rayon pool.
+pub fn pick_best_move_par(rng: &mut impl Rng, board: &Board, piece: Piece) -> Position {
+    pick_move_par(rng, board, piece, Selection::Best)
+}
+
+/// A best move for `piece`; ties are broken at random.
+pub fn pick_best_move(rng: &mut impl Rng, board: &Board, piece: Piece) -> Position {
+    pick_move(rng, board, piece, Selection::Best)
+}
+
+/// Like `pick_move`, but searches the root moves on the rayon pool.
+pub fn pick_move_par(
+    rng: &mut impl Rng,
+    board: &Board,
+    piece: Piece,
+    selection: Selection,
+) -> Position {
+    if let Some(pos) = lone_move(board) {
+        return pos;
+    }
+    select_move(rng, &score_moves_par(board, piece), selection)
+}
+
+/// Searches every candidate move and chooses one according to `selection`.
+pub fn pick_move(
+    rng: &mut impl Rng,
+    board: &Board,
+    piece: Piece,
+    selection: Selection,
+) -> Position {
+    if let Some(pos) = lone_move(board) {
+        return pos;
+    }
+    select_move(rng, &score_moves(board, piece), selection)
+}
+
+/// Like `pick_move`, but searches with iterative deepening until `budget`
+/// runs out.
+pub fn pick_move_timed(
+    rng: &mut impl Rng,
+    board: &Board,
+    piece: Piece,
+    budget: Duration,
+    selection: Selection,
+) -> Position {
+    if let Some(pos) = lone_move(board) {
+        return pos;
+    }
+    let scored = score_moves_timed(board, piece, &Clock::with_budget(budget));
+    select_move(rng, &scored, selection)
+}
+
+/// Like `pick_move_timed`, but searches the root moves on the rayon pool.
+pub fn pick_move_timed_par(
+    rng: &mut impl Rng,
+    board: &Board,
+    piece: Piece,
+    budget: Duration,
+    selection: Selection,
+) -> Position {
+    if let Some(pos) = lone_move(board) {
+        return pos;
+    }
+    let scored = score_moves_timed_par(board, piece, &Clock::with_budget(budget));
+    select_move(rng, &scored, selection)
+}
+
+/// Scores for every candidate move from the deepest iteration of an
+/// iterative-deepening search that finished before `clock` stopped.
+///
+/// Each iteration goes one ply deeper than the last, and tries the moves
+/// the previous one found best first, so the repeated work is cheap. The
+/// first iteration only looks at the positions right after each move and
+/// always finishes.
+pub fn score_moves_timed(board: &Board, piece: Piece, clock: &Clock) -> Vec<(Position, i32)> {
+    deepen(board, piece, clock, false)
+}
+
+/// Like `score_moves_timed`, but searches the root moves on the rayon pool.
+pub fn score_moves_timed_par(board: &Board, piece: Piece, clock: &Clock) -> Vec<(Position, i32)> {
+    deepen(board, piece, clock, true)
+}
+
+fn deepen(board: &Board, piece: Piece, clock: &Clock, parallel: bool) -> Vec<(Position, i32)> {
+    let table = tt::shared();
+    let moves: Vec<Position> = candidate_moves(board).collect();
+    let full_depth = board.empty().count_ones() as i32 - 1;
+
+    let mut scored = score_moves_with(board, moves, piece, 0, table, &Clock::unlimited());
+    for max_depth in 1..=full_depth {
+        // once every move is a known win or loss, deeper searches agree
+        if scored
+            .iter()
+            .all(|&(_, score)| Outcome::of(score) != Outcome::Undecided)
+        {
+            break;
+        }
+
+        // the best moves so far go first, so their lines fill the table
+        // before the rest are searched
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        let moves: Vec<Position> = scored.iter().map(|&(pos, _)| pos).collect();
+
+        let deeper = if parallel {
+            score_moves_with_par(board, moves.clone(), piece, max_depth, table, clock)
+        } else {
+            score_moves_with(board, moves.clone(), piece, max_depth, table, clock)
+        };
+        if clock.is_stopped() {
+            break;
+        }
+        scored = deeper;
+    }
+    scored
+}
+
+/// Exact scores for every candidate move, from `piece`'s point of view.
+pub fn score_moves_par(board: &Board, piece: Piece) -> Vec<(Position, i32)> {
+    score_moves_with_par(
+        board,
+        candidate_moves(board).collect(),
+        piece,
+        search_depth(board),
+        tt::shared(),
+        &Clock::unlimited(),
+    )
+}
+
+fn score_moves_with_par(
+    board: &Board,
+    moves: Vec<Position>,
+    piece: Piece,
+    max_depth: i32,
+    table: &TranspositionTable,
+    clock: &Clock,
+) -> Vec<(Position, i32)> {
+    moves
         .into_par_iter()
         .map(|possible_move| {
-            let mut new_board = board.clone();
-            apply_move(&mut new_board, &possible_move, piece);
-            let score = minimax(&new_board, 0, 9, piece, piece, i32::MIN, i32::MAX);
-            (possible_move, score)
+            (
+                possible_move,
+                score_move(board, &possible_move, piece, max_depth, table, clock),
+            )
         })
-        .max_by_key(|&(_, score)| score)
-        .expect("No valid moves available");
+        .collect()
+}
 
-    best_move
+/// Exact scores for every candidate move, from `piece`'s point of view.
+///
+/// Every root move gets the full window so that ties and near-ties are
+/// scored exactly; the table keeps the repeated work cheap.
+pub fn score_moves(board: &Board, piece: Piece) -> Vec<(Position, i32)> {
+    score_moves_with(
+        board,
+        candidate_moves(board).collect(),
+        piece,
+        search_depth(board),
+        tt::shared(),
+        &Clock::unlimited(),
+    )
 }
 
-pub fn pick_best_move(rng: &mut ThreadRng, board: &Board, piece: Piece) -> Position {
-    let mut best_score = i32::MIN;
-    let mut best_move = None;
-    let mut alpha = i32::MIN;
-    let beta = i32::MAX;
+fn score_moves_with(
+    board: &Board,
+    moves: Vec<Position>,
+    piece: Piece,
+    max_depth: i32,
+    table: &TranspositionTable,
+    clock: &Clock,
+) -> Vec<(Position, i32)> {
+    moves
+        .into_iter()
+        .map(|possible_move| {
+            (
+                possible_move,
+                score_move(board, &possible_move, piece, max_depth, table, clock),
+            )
+        })
+        .collect()
+}
+
+fn score_move(
+    board: &Board,
+    pos: &Position,
+    piece: Piece,
+    max_depth: i32,
+    table: &TranspositionTable,
+    clock: &Clock,
+) -> i32 {
+    let mut new_board = *board;
+    place_piece(&mut new_board, pos, piece);
+    minimax(
+        &new_board,
+        0,
+        max_depth,
+        piece,
+        piece,
+        i32::MIN,
+        i32::MAX,
+        table,
+        clock,
+    )
+}
 
-    for possible_move in get_available_moves(board) {
-        let mut new_board = board.clone();
-        apply_move(&mut new_board, &possible_move, piece);
-        let score = minimax(&new_board, 0, 9, piece, piece, alpha, beta);
+fn select_move(rng: &mut impl Rng, scored: &[(Position, i32)], selection: Selection) -> Position {
+    let best_score = scored
+        .iter()
+        .map(|&(_, score)| score)
+        .max()
+        .expect("No valid moves available");
+
+    match selection {
+        Selection::Best => {
+            scored
+                .iter()
+                .filter(|&&(_, score)| score == best_score)
+                .choose(rng)
+                .unwrap()
+                .0
+        }
+        Selection::Softmax { temperature } => {
+            debug_assert!(
+                temperature.is_finite() && temperature > 0.0,
+                "softmax temperature must be positive, not {}",
+                temperature
+            );
+            let outcome = Outcome::of(best_score);
+            let near_best: Vec<&(Position, i32)> = scored
+                .iter()
+                .filter(|&&(_, score)| Outcome::of(score) == outcome)
+                .collect();
+            let weights = near_best
+                .iter()
+                .map(|&&(_, score)| ((score - best_score) as f64 / temperature).exp());
+            match WeightedIndex::new(weights) {
+                Ok(dist) => near_best[dist.sample(rng)].0,
+                // a tiny temperature underflows every weight but the best
+                Err(_) => {
+                    near_best
+                        .iter()
+                        .filter(|&&&(_, score)| score == best_score)
+                        .choose(rng)
+                        .unwrap()
+                        .0
+                }
+            }
+        }
+    }
+}
+
+/// The game-theoretic class of a score.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The side to move can force a win.
+    Win,
+    /// Perfect play from both sides fills the board.
+    Draw,
+    /// Neither a forced win nor a forced loss was found.
+    Undecided,
+    /// The opponent can force a win.
+    Loss,
+}
 
-        if score > best_score {
-            best_score = score;
-            best_move = Some(possible_move);
+impl Outcome {
+    /// The class of a search score. A score alone cannot tell a draw from
+    /// a position the search did not see to the end, so this never returns
+    /// `Draw`.
+    pub fn of(score: i32) -> Self {
+        if score > WIN_THRESHOLD {
+            Outcome::Win
+        } else if score < -WIN_THRESHOLD {
+            Outcome::Loss
+        } else {
+            Outcome::Undecided
         }
+    }
+}
+
+/// What the engine thinks of one move.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveAnalysis {
+    /// The move.
+    pub pos: Position,
+    /// Its minimax score, from the mover's point of view.
+    pub score: i32,
+    /// What the move leads to.
+    pub outcome: Outcome,
+    /// Plies from now until the game ends, counting this move, when the
+    /// outcome is known.
+    pub distance: Option<u32>,
+    /// The expected line of play, starting with this move.
+    pub pv: Vec<Position>,
+}
+
+/// Every candidate move for `piece` with its evaluation, best first. On
+/// boards too large to search every cell these are only the moves
+/// `candidate_moves` picks, so most of the legal moves are left out.
+pub fn analyze(board: &Board, piece: Piece) -> Vec<MoveAnalysis> {
+    let table = tt::shared();
+    let remaining = board.empty().count_ones();
+    // only a search of every move to the end of the game can prove a draw
+    let exhaustive = board.cells().count_ones() <= FULL_WIDTH_CELLS
+        && search_depth(board) >= remaining as i32 - 1;
+
+    let mut analysis: Vec<MoveAnalysis> = score_moves(board, piece)
+        .into_iter()
+        .map(|(pos, score)| {
+            let (outcome, distance) = match Outcome::of(score) {
+                // the score counts plies after this move
+                Outcome::Win => (Outcome::Win, Some((WIN_SCORE - score) as u32 + 1)),
+                Outcome::Loss => (Outcome::Loss, Some((WIN_SCORE + score) as u32 + 1)),
+                _ if exhaustive => (Outcome::Draw, Some(remaining)),
+                _ => (Outcome::Undecided, None),
+            };
+            MoveAnalysis {
+                pos,
+                score,
+                outcome,
+                distance,
+                pv: principal_variation(board, pos, piece, table),
+            }
+        })
+        .collect();
+    analysis.sort_by_key(|a| std::cmp::Reverse(a.score));
+    analysis
+}
+
+/// Follows the best moves the table remembers from `pos` onwards.
+fn principal_variation(
+    board: &Board,
+    pos: Position,
+    piece: Piece,
+    table: &TranspositionTable,
+) -> Vec<Position> {
+    let mut board = *board;
+    let mut pv = vec![pos];
+    place_piece(&mut board, &pos, piece);
 
-        alpha = std::cmp::max(alpha, score);
-        if beta <= alpha {
+    let mut to_move = piece.opponent();
+    while !is_game_over(&board) {
+        let (key, symmetry) = canonical_form(&board, to_move, piece);
+        let next = table
+            .probe(key)
+            .and_then(|entry| entry.best)
+            .map(|cell| symmetry.invert(&board, cell))
+            .filter(|next| board.get(next).is_none());
+        let Some(next) = next else {
             break;
+        };
+        place_piece(&mut board, &next, to_move);
+        pv.push(next);
+        to_move = to_move.opponent();
+    }
+    pv
+}
+
+/// The moves worth searching. On small boards that is every empty cell; on
+/// larger ones only cells touching a piece, or the centre of an empty board.
+pub fn candidate_moves(board: &Board) -> Moves {
+    if board.cells().count_ones() <= FULL_WIDTH_CELLS {
+        return get_available_moves(board);
+    }
+
+    let occupied = board.occupied();
+    if occupied.is_empty() {
+        let centre = Position {
+            x: board.width() / 2,
+            y: board.height() / 2,
+        };
+        return board.moves(BitSet::single(board.index(&centre)));
+    }
+
+    let mut near = occupied;
+    for step in board.directions() {
+        near |= (occupied << step) | (occupied >> step);
+    }
+    board.moves(near & board.empty())
+}
+
+/// How many plies past the root move to search, so that a search stays
+/// within `NODE_BUDGET`. Small boards are searched to the end.
+fn search_depth(board: &Board) -> i32 {
+    let remaining = board.empty().count_ones() as i32;
+    let shrinks = board.cells().count_ones() <= FULL_WIDTH_CELLS;
+    let candidates = candidate_moves(board).len();
+    let mut branching = match candidate_moves(board).next() {
+        // on large boards the replies are the cells near the move, which
+        // outnumber the root's candidates on a nearly empty board
+        Some(pos) if !shrinks => {
+            let mut after = *board;
+            place_piece(&mut after, &pos, Piece::X);
+            candidate_moves(&after).len().max(candidates)
         }
+        _ => candidates,
     }
+    .max(1) as u64;
+    let mut nodes = branching;
+    let mut depth = 0;
 
-    best_move.expect("No valid moves available")
+    while depth < remaining && nodes * branching <= NODE_BUDGET {
+        if shrinks {
+            branching = branching.saturating_sub(1).max(1);
+        }
+        nodes *= branching;
+        depth += 1;
+    }
+    depth
 }
 
+#[allow(clippy::too_many_arguments)]
 fn minimax(
     board: &Board,
     depth: i32,
@@ -75,20 +679,50 @@ fn minimax(
     piece: Piece,
     mut alpha: i32,
     mut beta: i32,
+    table: &TranspositionTable,
+    clock: &Clock,
 ) -> i32 {
     if depth == max_depth || is_game_over(board) {
         return evaluate(board, perspective, depth, max_depth);
     }
+    if clock.expired() {
+        // the caller throws the whole iteration away
+        return 0;
+    }
 
-    let piece = match piece {
-        Piece::X => Piece::O,
-        Piece::O => Piece::X,
-    };
-    if piece == perspective {
+    let piece = piece.opponent();
+    let remaining = (max_depth - depth) as u8;
+    let (key, symmetry) = canonical_form(board, piece, perspective);
+    let mut hash_move = None;
+    if let Some(entry) = table.probe(key) {
+        if entry.remaining >= remaining {
+            let score = score_from_table(entry.score, depth);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower => alpha = std::cmp::max(alpha, score),
+                Bound::Upper => beta = std::cmp::min(beta, score),
+            }
+            if beta <= alpha {
+                return score;
+            }
+        }
+        // a shallower search still knows which move to try first
+        hash_move = entry
+            .best
+            .map(|cell| symmetry.invert(board, cell))
+            .filter(|pos| board.get(pos).is_none());
+    }
+    let (window_alpha, window_beta) = (alpha, beta);
+    let moves = hash_move
+        .into_iter()
+        .chain(candidate_moves(board).filter(move |&pos| Some(pos) != hash_move));
+
+    let mut best_move = None;
+    let value = if piece == perspective {
         let mut max_eval = i32::MIN;
-        for possible_move in get_available_moves(board) {
-            let mut new_board = board.clone();
-            apply_move(&mut new_board, &possible_move, piece);
+        for possible_move in moves {
+            let mut new_board = *board;
+            place_piece(&mut new_board, &possible_move, piece);
             let eval = minimax(
                 &new_board,
                 depth + 1,
@@ -97,8 +731,13 @@ fn minimax(
                 piece,
                 alpha,
                 beta,
+                table,
+                clock,
             );
-            max_eval = std::cmp::max(max_eval, eval);
+            if eval > max_eval {
+                max_eval = eval;
+                best_move = Some(possible_move);
+            }
             alpha = std::cmp::max(alpha, eval);
             if beta <= alpha {
                 break;
@@ -107,9 +746,9 @@ fn minimax(
         max_eval
     } else {
         let mut min_eval = i32::MAX;
-        for possible_move in get_available_moves(board) {
-            let mut new_board = board.clone();
-            apply_move(&mut new_board, &possible_move, piece);
+        for possible_move in moves {
+            let mut new_board = *board;
+            place_piece(&mut new_board, &possible_move, piece);
             let eval = minimax(
                 &new_board,
                 depth + 1,
@@ -118,47 +757,107 @@ fn minimax(
                 piece,
                 alpha,
                 beta,
+                table,
+                clock,
             );
-            min_eval = std::cmp::min(min_eval, eval);
+            if eval < min_eval {
+                min_eval = eval;
+                best_move = Some(possible_move);
+            }
             beta = std::cmp::min(beta, eval);
             if beta <= alpha {
                 break;
             }
         }
         min_eval
+    };
+
+    // an interrupted search must not leave half-finished scores behind
+    if clock.is_stopped() {
+        return value;
+    }
+
+    // a cutoff only bounds the value from one side
+    let bound = if value <= window_alpha {
+        Bound::Upper
+    } else if value >= window_beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    table.store(
+        key,
+        Entry {
+            score: score_to_table(value, depth),
+            remaining,
+            bound,
+            best: best_move.map(|pos| symmetry.apply(board, pos)),
+        },
+    );
+    value
+}
+
+/// Win and loss scores count plies from the root, but a table entry can be
+/// reached at any depth, so the table keeps them counted from the entry.
+fn score_to_table(score: i32, depth: i32) -> i32 {
+    if score > WIN_THRESHOLD {
+        score + depth
+    } else if score < -WIN_THRESHOLD {
+        score - depth
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, depth: i32) -> i32 {
+    if score > WIN_THRESHOLD {
+        score - depth
+    } else if score < -WIN_THRESHOLD {
+        score + depth
+    } else {
+        score
     }
 }
 
-fn evaluate(board: &Board, perspective: Piece, depth: i32, max_depth: i32) -> i32 {
+/// Wins score higher the sooner they happen and losses score higher the
+/// later they happen, so the AI finishes quickly and holds out when lost.
+fn evaluate(board: &Board, perspective: Piece, depth: i32, _max_depth: i32) -> i32 {
     let winner = is_game_won(board);
     if let Some(winner) = winner {
         if winner == perspective {
-            1
+            WIN_SCORE - depth
         } else {
-            -1
+            -WIN_SCORE + depth
         }
-    } else {
+    } else if no_more_moves(board) {
         0
+    } else {
+        heuristic(board, perspective)
     }
 }
 
-// winner = getWinner(board)
-// if not winner:
-//     return -100 * math.pow(2, 9 - depth)    #   tie is always depth 9..
-// elif winner == togglePlayer(myPlayer):
-//     return -1000 * math.pow(2, 9 - depth)
-// elif winner == myPlayer:
-//     return 100000000 / depth #/ math.pow(2, depth)
-
-// fn evaluate(board: &Board, piece: Piece, depth: i32) -> i32 {
-//     let winner = is_game_won(board);
-//     if let Some(winner) = winner {
-//         if winner == piece {
-//             10_000
-//         } else {
-//             -10_000
-//         }
-//     } else {
-//         0
-//     }
-// }
+/// Scores an unfinished position by its open lines: every k-cell window
+/// free of the opponent is worth more the more of our pieces it holds.
+fn heuristic(board: &Board, perspective: Piece) -> i32 {
+    line_potential(board, perspective) - line_potential(board, perspective.opponent())
+}
+
+fn line_potential(board: &Board, piece: Piece) -> i32 {
+    let k = board.k();
+    let mine = board.pieces(piece);
+    let open = board.cells() & !board.pieces(piece.opponent());
+
+    let mut score = 0;
+    for step in board.directions() {
+        let mut windows = runs(open, step, k);
+        while let Some(start) = windows.pop_first() {
+            let count = (0..k as usize)
+                .filter(|i| mine.contains(start + i * step))
+                .count();
+            if count > 0 {
+                score += 1 << (2 * count.min(6));
+            }
+        }
+    }
+    score
+}
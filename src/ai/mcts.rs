@@ -0,0 +1,247 @@
+//! Monte Carlo tree search with the UCT selection rule: grows a tree of
+//! the most promising lines and scores leaves by playing random games out,
+//! so it needs no evaluation function and scales to larger boards.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng, SeedableRng};
+use rayon::prelude::*;
+
+use super::candidate_moves;
+use crate::game::{
+    get_available_moves, is_game_over, is_game_won, place_piece, Board, Piece, Position,
+};
+
+/// How long a search may run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    /// A fixed number of playouts, split evenly between the trees of a
+    /// parallel search.
+    Iterations(u32),
+    /// Wall-clock time, given to every tree of a parallel search.
+    Time(Duration),
+}
+
+/// The usual UCT exploration constant, `sqrt(2)`.
+pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+struct Node {
+    /// The move that led here; `None` at the root.
+    pos: Option<Position>,
+    /// The piece that played `pos`.
+    mover: Piece,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Position>,
+    visits: u32,
+    /// Playouts won by `mover`, with draws counting a half.
+    wins: f64,
+}
+
+/// One search tree, kept in an arena so that nodes can point at their
+/// parents.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new(board: &Board, piece: Piece) -> Self {
+        Tree {
+            nodes: vec![Node {
+                pos: None,
+                mover: piece.opponent(),
+                parent: None,
+                children: Vec::new(),
+                untried: candidate_moves(board).collect(),
+                visits: 0,
+                wins: 0.0,
+            }],
+        }
+    }
+
+    /// The child of `node` with the highest upper confidence bound.
+    fn select_child(&self, node: usize, exploration: f64) -> usize {
+        let log_visits = (self.nodes[node].visits as f64).ln();
+        let ucb = |&child: &usize| {
+            let child = &self.nodes[child];
+            let visits = child.visits as f64;
+            child.wins / visits + exploration * (log_visits / visits).sqrt()
+        };
+        *self.nodes[node]
+            .children
+            .iter()
+            .max_by(|a, b| ucb(a).total_cmp(&ucb(b)))
+            .unwrap()
+    }
+
+    /// Runs one select, expand, play out and back up cycle from `root`.
+    fn iterate(&mut self, rng: &mut impl Rng, root: &Board, exploration: f64) {
+        let mut board = *root;
+        let mut node = 0;
+
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select_child(node, exploration);
+            let child = &self.nodes[node];
+            place_piece(&mut board, &child.pos.unwrap(), child.mover);
+        }
+
+        if !self.nodes[node].untried.is_empty() {
+            let untried = &mut self.nodes[node].untried;
+            let pos = untried.swap_remove(rng.gen_range(0..untried.len()));
+            let mover = self.nodes[node].mover.opponent();
+            place_piece(&mut board, &pos, mover);
+
+            let child = self.nodes.len();
+            self.nodes.push(Node {
+                pos: Some(pos),
+                mover,
+                parent: Some(node),
+                children: Vec::new(),
+                untried: if is_game_over(&board) {
+                    Vec::new()
+                } else {
+                    candidate_moves(&board).collect()
+                },
+                visits: 0,
+                wins: 0.0,
+            });
+            self.nodes[node].children.push(child);
+            node = child;
+        }
+
+        let winner = play_out(rng, &mut board, self.nodes[node].mover.opponent());
+
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.wins += match winner {
+                Some(piece) if piece == node.mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+    /// Visit counts of the root's moves.
+    fn root_visits(&self) -> impl Iterator<Item = (Position, u32)> + '_ {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|&child| (self.nodes[child].pos.unwrap(), self.nodes[child].visits))
+    }
+}
+
+/// Plays random moves, starting with `piece`, until the game ends, and
+/// returns the winner.
+fn play_out(rng: &mut impl Rng, board: &mut Board, mut piece: Piece) -> Option<Piece> {
+    while !is_game_over(board) {
+        let pos = get_available_moves(board).choose(rng).unwrap();
+        place_piece(board, &pos, piece);
+        piece = piece.opponent();
+    }
+    is_game_won(board)
+}
+
+/// Grows one tree from `board` within `budget` and returns its root visit
+/// counts.
+fn search(
+    rng: &mut impl Rng,
+    board: &Board,
+    piece: Piece,
+    budget: Budget,
+    exploration: f64,
+) -> Vec<(Position, u32)> {
+    let mut tree = Tree::new(board, piece);
+    match budget {
+        Budget::Iterations(iterations) => {
+            for _ in 0..iterations.max(1) {
+                tree.iterate(rng, board, exploration);
+            }
+        }
+        Budget::Time(limit) => {
+            let start = Instant::now();
+            // always finish at least one playout
+            loop {
+                tree.iterate(rng, board, exploration);
+                if start.elapsed() >= limit {
+                    break;
+                }
+            }
+        }
+    }
+    tree.root_visits().collect()
+}
+
+/// The most visited move; ties are broken at random.
+fn most_visited(rng: &mut impl Rng, visits: &[(Position, u32)]) -> Position {
+    let most = visits
+        .iter()
+        .map(|&(_, visits)| visits)
+        .max()
+        .expect("No valid moves available");
+    visits
+        .iter()
+        .filter(|&&(_, v)| v == most)
+        .choose(rng)
+        .unwrap()
+        .0
+}
+
+/// The move for `piece` that a single search tree visits most.
+pub fn pick_move(
+    rng: &mut impl Rng,
+    board: &Board,
+    piece: Piece,
+    budget: Budget,
+    exploration: f64,
+) -> Position {
+    let visits = search(rng, board, piece, budget, exploration);
+    most_visited(rng, &visits)
+}
+
+/// Like `pick_move`, but grows `trees` independent trees on the rayon pool
+/// and adds up their root visit counts. The caller picks the number of
+/// trees, not the machine, so a seeded search plays the same move on any
+/// host; more trees search more widely but each one less deeply.
+pub fn pick_move_par(
+    rng: &mut impl Rng,
+    board: &Board,
+    piece: Piece,
+    budget: Budget,
+    exploration: f64,
+    trees: u32,
+) -> Position {
+    let trees = trees.max(1);
+    let budget = match budget {
+        Budget::Iterations(iterations) => Budget::Iterations(iterations.div_ceil(trees)),
+        time => time,
+    };
+
+    // seeding every tree from `rng` keeps seeded games repeatable
+    let seeds: Vec<u64> = (0..trees).map(|_| rng.gen()).collect();
+    let totals = seeds
+        .into_par_iter()
+        .map(|seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            search(&mut rng, board, piece, budget, exploration)
+        })
+        .fold(HashMap::new, |mut totals, visits| {
+            for (pos, v) in visits {
+                *totals.entry(pos).or_insert(0) += v;
+            }
+            totals
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (pos, v) in b {
+                *a.entry(pos).or_insert(0) += v;
+            }
+            a
+        });
+
+    let mut visits: Vec<(Position, u32)> = totals.into_iter().collect();
+    visits.sort_by_key(|&(pos, _)| (pos.y, pos.x));
+    most_visited(rng, &visits)
+}
@@ -0,0 +1,342 @@
+//! Exhaustive solving of small boards: every position reachable from a
+//! start position, with its game-theoretic value, the distance to the end
+//! of the game and all of its optimal moves.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::WIN_SCORE;
+use crate::game::{
+    get_available_moves, is_game_won, no_more_moves, place_piece, Board, Piece, Position,
+};
+
+/// Boards with more cells than this are too big to solve.
+pub const MAX_CELLS: u32 = 16;
+
+const MAGIC: &[u8; 4] = b"PPTS";
+const VERSION: u8 = 1;
+
+/// The result of perfect play, for the side to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Value {
+    /// The side to move wins.
+    Win,
+    /// Neither side can force a win.
+    Draw,
+    /// The side to move loses.
+    Loss,
+}
+
+impl Value {
+    fn opponent(self) -> Value {
+        match self {
+            Value::Win => Value::Loss,
+            Value::Draw => Value::Draw,
+            Value::Loss => Value::Win,
+        }
+    }
+}
+
+/// What perfect play makes of one position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Solution {
+    /// The value for the side to move.
+    pub value: Value,
+    /// Plies until the game ends. The winner hurries and the loser holds
+    /// out, and a draw always fills the board.
+    pub distance: u8,
+    /// One bit per optimal move, indexed by `y * width + x`.
+    optimal: u16,
+}
+
+impl Solution {
+    /// Whether `self` is better for the side to move than `other`.
+    fn beats(&self, other: &Solution) -> bool {
+        let rank = |s: &Solution| match s.value {
+            Value::Win => (2, -(s.distance as i32)),
+            Value::Draw => (1, 0),
+            Value::Loss => (0, s.distance as i32),
+        };
+        rank(self) > rank(other)
+    }
+}
+
+/// Every position reachable from a start position, solved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SolutionTable {
+    width: u8,
+    height: u8,
+    k: u8,
+    solutions: HashMap<u32, Solution>,
+}
+
+impl SolutionTable {
+    /// Number of positions in the table.
+    pub fn len(&self) -> usize {
+        self.solutions.len()
+    }
+
+    /// Whether the table holds no positions.
+    pub fn is_empty(&self) -> bool {
+        self.solutions.is_empty()
+    }
+
+    /// The solution for `board`, if it was reachable from the start.
+    pub fn get(&self, board: &Board) -> Option<Solution> {
+        if (board.width(), board.height(), board.k()) != (self.width, self.height, self.k) {
+            return None;
+        }
+        self.solutions.get(&encode(board)).copied()
+    }
+
+    /// Every move that keeps the best result for the side to move, or
+    /// nothing if `board` is not in the table or the game is over.
+    pub fn optimal_moves(&self, board: &Board) -> Vec<Position> {
+        let Some(solution) = self.get(board) else {
+            return Vec::new();
+        };
+        get_available_moves(board)
+            .filter(|pos| solution.optimal & 1 << cell(board, pos) != 0)
+            .collect()
+    }
+
+    /// Scores for every move of `piece`, on the same scale as
+    /// `ai::score_moves`, or `None` if `board` is not in the table or it is
+    /// not `piece`'s turn.
+    ///
+    /// A position from a game O started is looked up with the colours
+    /// swapped, which turns it into one from a game X started.
+    pub(crate) fn score_moves(&self, board: &Board, piece: Piece) -> Option<Vec<(Position, i32)>> {
+        let (xs, os) = (
+            board.pieces(Piece::X).count_ones(),
+            board.pieces(Piece::O).count_ones(),
+        );
+        let o_started = match piece {
+            Piece::X => os == xs + 1,
+            Piece::O => xs == os,
+        };
+        if o_started {
+            return self.score_moves(&swap_colours(board), piece.opponent());
+        }
+        if side_to_move(board) != piece {
+            return None;
+        }
+        get_available_moves(board)
+            .map(|pos| {
+                let mut child = *board;
+                place_piece(&mut child, &pos, piece);
+                let reply = self.get(&child)?;
+                // the search counts plies after the root move
+                let score = WIN_SCORE - reply.distance as i32;
+                let score = match reply.value.opponent() {
+                    Value::Win => score,
+                    Value::Draw => 0,
+                    Value::Loss => -score,
+                };
+                Some((pos, score))
+            })
+            .collect()
+    }
+
+    /// Writes the table in a compact binary format: a short header, then
+    /// eight bytes per position.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, self.width, self.height, self.k])?;
+        writer.write_all(&(self.solutions.len() as u32).to_le_bytes())?;
+
+        // sorted, so that the same table always gives the same file
+        let mut codes: Vec<&u32> = self.solutions.keys().collect();
+        codes.sort_unstable();
+        for code in codes {
+            let solution = &self.solutions[code];
+            let value = match solution.value {
+                Value::Win => 0,
+                Value::Draw => 1,
+                Value::Loss => 2,
+            };
+            writer.write_all(&code.to_le_bytes())?;
+            writer.write_all(&[value, solution.distance])?;
+            writer.write_all(&solution.optimal.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a table written by `write_to`.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a solution table"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("unsupported solution table version"));
+        }
+        let (width, height, k) = (header[5], header[6], header[7]);
+        let board = Board::with_size(width, height, k).map_err(|e| invalid(&e))?;
+        let cells = board.cells().count_ones();
+        if cells > MAX_CELLS {
+            return Err(invalid("solution table for a board too big to solve"));
+        }
+        let count = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        // every cell is empty, X or O, so no board has more positions
+        if u64::from(count) > 3u64.pow(cells) {
+            return Err(invalid("more positions than the board has"));
+        }
+
+        // the count is only a claim until the records are read, so the map
+        // grows as they arrive rather than all at once
+        let mut solutions = HashMap::with_capacity(count.min(1 << 16) as usize);
+        for _ in 0..count {
+            let mut record = [0u8; 8];
+            reader.read_exact(&mut record)?;
+            let value = match record[4] {
+                0 => Value::Win,
+                1 => Value::Draw,
+                2 => Value::Loss,
+                _ => return Err(invalid("bad value in solution table")),
+            };
+            solutions.insert(
+                u32::from_le_bytes(record[..4].try_into().unwrap()),
+                Solution {
+                    value,
+                    distance: record[5],
+                    optimal: u16::from_le_bytes(record[6..].try_into().unwrap()),
+                },
+            );
+        }
+
+        Ok(SolutionTable {
+            width,
+            height,
+            k,
+            solutions,
+        })
+    }
+
+    /// Writes the table to the file at `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Reads a table from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+/// Solves every position reachable from `board`, with X moving first.
+pub fn solve(board: &Board) -> Result<SolutionTable, String> {
+    let cells = board.cells().count_ones();
+    if cells > MAX_CELLS {
+        return Err(format!(
+            "a board with {} cells is too big to solve, the limit is {}",
+            cells, MAX_CELLS
+        ));
+    }
+
+    let mut table = SolutionTable {
+        width: board.width(),
+        height: board.height(),
+        k: board.k(),
+        solutions: HashMap::new(),
+    };
+    solve_position(board, side_to_move(board), &mut table.solutions);
+    Ok(table)
+}
+
+/// The solved classic board, built on first use.
+pub fn classic() -> &'static SolutionTable {
+    static TABLE: OnceLock<SolutionTable> = OnceLock::new();
+    TABLE.get_or_init(|| solve(&Board::new()).unwrap())
+}
+
+fn solve_position(board: &Board, piece: Piece, solutions: &mut HashMap<u32, Solution>) -> Solution {
+    let code = encode(board);
+    if let Some(&solution) = solutions.get(&code) {
+        return solution;
+    }
+
+    let solution = if is_game_won(board).is_some() {
+        // only the side that just moved can have won
+        Solution {
+            value: Value::Loss,
+            distance: 0,
+            optimal: 0,
+        }
+    } else if no_more_moves(board) {
+        Solution {
+            value: Value::Draw,
+            distance: 0,
+            optimal: 0,
+        }
+    } else {
+        let mut best: Option<Solution> = None;
+        for pos in get_available_moves(board) {
+            let mut child = *board;
+            place_piece(&mut child, &pos, piece);
+            let reply = solve_position(&child, piece.opponent(), solutions);
+            let candidate = Solution {
+                value: reply.value.opponent(),
+                distance: reply.distance + 1,
+                optimal: 1 << cell(board, &pos),
+            };
+            best = match best {
+                Some(b) if b.beats(&candidate) => Some(b),
+                Some(b) if !candidate.beats(&b) => Some(Solution {
+                    optimal: b.optimal | candidate.optimal,
+                    ..b
+                }),
+                _ => Some(candidate),
+            };
+        }
+        best.unwrap()
+    };
+
+    solutions.insert(code, solution);
+    solution
+}
+
+/// X moves first, so it is X's turn whenever the counts are level.
+fn side_to_move(board: &Board) -> Piece {
+    if board.pieces(Piece::X).count_ones() > board.pieces(Piece::O).count_ones() {
+        Piece::O
+    } else {
+        Piece::X
+    }
+}
+
+/// `board` with every X made an O and every O an X.
+fn swap_colours(board: &Board) -> Board {
+    let mut swapped = Board::with_size(board.width(), board.height(), board.k()).unwrap();
+    for pos in board.moves(board.occupied()) {
+        place_piece(&mut swapped, &pos, board.get(&pos).unwrap().opponent());
+    }
+    swapped
+}
+
+fn cell(board: &Board, pos: &Position) -> u32 {
+    pos.y as u32 * board.width() as u32 + pos.x as u32
+}
+
+/// A base-3 number with one digit per cell: 0 empty, 1 X, 2 O.
+fn encode(board: &Board) -> u32 {
+    let mut code = 0;
+    for y in (0..board.height()).rev() {
+        for x in (0..board.width()).rev() {
+            code = code * 3
+                + match board.get(&Position { x, y }) {
+                    None => 0,
+                    Some(Piece::X) => 1,
+                    Some(Piece::O) => 2,
+                };
+        }
+    }
+    code
+}
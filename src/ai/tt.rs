@@ -0,0 +1,274 @@
+//! A lock-free transposition table and symmetry-aware position hashing.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use crate::bitset::BitSet;
+use crate::game::{Board, Piece, Position};
+
+/// What a stored score says about the true value of a position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    /// The search finished inside the window; the score is the value.
+    Exact,
+    /// The search failed high; the value is at least the score.
+    Lower,
+    /// The search failed low; the value is at most the score.
+    Upper,
+}
+
+/// A stored search result.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Entry {
+    /// Score from the searching side's point of view.
+    pub score: i32,
+    /// Plies that were searched below the stored position.
+    pub remaining: u8,
+    /// How `score` relates to the true value.
+    pub bound: Bound,
+    /// The best move found, as a cell of the canonical orientation, tried
+    /// first when the position is searched again.
+    pub best: Option<u8>,
+}
+
+impl Entry {
+    const VALID: u64 = 1 << 42;
+    const NO_MOVE: u8 = u8::MAX;
+
+    fn pack(self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let best = self.best.unwrap_or(Self::NO_MOVE) as u64;
+        self.score as u32 as u64
+            | (self.remaining as u64) << 32
+            | bound << 40
+            | Self::VALID
+            | best << 43
+    }
+
+    fn unpack(data: u64) -> Option<Self> {
+        if data & Self::VALID == 0 {
+            return None;
+        }
+        let bound = match (data >> 40) & 0b11 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        let best = (data >> 43) as u8;
+        Some(Entry {
+            score: data as u32 as i32,
+            remaining: (data >> 32) as u8,
+            bound,
+            best: (best != Self::NO_MOVE).then_some(best),
+        })
+    }
+}
+
+/// One table slot. The key is stored xor'ed with the data, so a slot torn
+/// by two threads writing at once fails the key check instead of returning
+/// another position's entry.
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Counters since the last `reset_stats`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TableStats {
+    /// Lookups made.
+    pub probes: u64,
+    /// Lookups that found an entry for the same position.
+    pub hits: u64,
+    /// Entries written.
+    pub stores: u64,
+}
+
+impl TableStats {
+    /// Fraction of probes that were hits.
+    pub fn hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.probes as f64
+        }
+    }
+}
+
+/// A fixed-size, lock-free transposition table that rayon workers can
+/// probe and fill concurrently.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    probes: AtomicU64,
+    hits: AtomicU64,
+    stores: AtomicU64,
+}
+
+impl TranspositionTable {
+    /// A table with `1 << bits` slots.
+    pub fn new(bits: u32) -> Self {
+        TranspositionTable {
+            slots: (0..1usize << bits).map(|_| Slot::default()).collect(),
+            probes: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    /// The entry stored for `key`, if any.
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        if slot.check.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        let entry = Entry::unpack(data)?;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry)
+    }
+
+    /// Stores `entry` for `key`, replacing whatever shared its slot.
+    pub fn store(&self, key: u64, entry: Entry) {
+        self.stores.fetch_add(1, Ordering::Relaxed);
+        let slot = self.slot(key);
+        let data = entry.pack();
+        slot.data.store(data, Ordering::Relaxed);
+        slot.check.store(key ^ data, Ordering::Relaxed);
+    }
+
+    /// Empties every slot. The statistics are kept.
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.check.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// The counters since the last `reset_stats`.
+    pub fn stats(&self) -> TableStats {
+        TableStats {
+            probes: self.probes.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Zeroes the counters.
+    pub fn reset_stats(&self) {
+        self.probes.store(0, Ordering::Relaxed);
+        self.hits.store(0, Ordering::Relaxed);
+        self.stores.store(0, Ordering::Relaxed);
+    }
+}
+
+/// The process-wide table used by `pick_best_move` and `pick_best_move_par`.
+pub fn shared() -> &'static TranspositionTable {
+    static TABLE: OnceLock<TranspositionTable> = OnceLock::new();
+    TABLE.get_or_init(|| TranspositionTable::new(20))
+}
+
+const fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// One random key per cell and piece, indexed by `y * width + x`.
+static ZOBRIST: [[u64; 2]; BitSet::CAPACITY] = {
+    let mut keys = [[0; 2]; BitSet::CAPACITY];
+    let mut i = 0;
+    while i < BitSet::CAPACITY {
+        keys[i][0] = splitmix64(2 * i as u64);
+        keys[i][1] = splitmix64(2 * i as u64 + 1);
+        i += 1;
+    }
+    keys
+};
+
+/// Maps a cell through one of the board's symmetries. Symmetries 4 to 7
+/// swap the axes and only exist on square boards.
+fn transform(sym: u8, pos: Position, width: u8, height: u8) -> (u8, u8) {
+    let (x, y) = (pos.x, pos.y);
+    let (fx, fy) = (width - 1 - x, height - 1 - y);
+    match sym {
+        0 => (x, y),
+        1 => (fx, y),
+        2 => (x, fy),
+        3 => (fx, fy),
+        4 => (y, x),
+        5 => (fy, x),
+        6 => (y, fx),
+        _ => (fy, fx),
+    }
+}
+
+/// One of the rotations and reflections of a board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Symmetry(u8);
+
+impl Symmetry {
+    /// The cell that `pos` maps to, as `y * width + x`.
+    pub fn apply(self, board: &Board, pos: Position) -> u8 {
+        let (x, y) = transform(self.0, pos, board.width(), board.height());
+        y * board.width() + x
+    }
+
+    /// The position that `apply` maps to `cell`.
+    pub fn invert(self, board: &Board, cell: u8) -> Position {
+        // the two quarter turns undo each other, every other symmetry undoes itself
+        let inverse = match self.0 {
+            5 => 6,
+            6 => 5,
+            sym => sym,
+        };
+        let pos = Position {
+            x: cell % board.width(),
+            y: cell / board.width(),
+        };
+        let (x, y) = transform(inverse, pos, board.width(), board.height());
+        Position { x, y }
+    }
+}
+
+/// A hash that is equal for every rotation and reflection of a position,
+/// and that also covers the board shape, the side to move and whose point
+/// of view the score is from.
+pub fn canonical_key(board: &Board, to_move: Piece, perspective: Piece) -> u64 {
+    canonical_form(board, to_move, perspective).0
+}
+
+/// `canonical_key`, along with the symmetry that maps the position onto the
+/// orientation the key was taken from.
+pub fn canonical_form(board: &Board, to_move: Piece, perspective: Piece) -> (u64, Symmetry) {
+    let (width, height) = (board.width(), board.height());
+    let symmetries = if width == height { 8 } else { 4 };
+
+    let mut hashes = [0u64; 8];
+    for (p, piece) in [Piece::X, Piece::O].into_iter().enumerate() {
+        for pos in board.moves(board.pieces(piece)) {
+            for (sym, hash) in hashes.iter_mut().enumerate().take(symmetries) {
+                let (x, y) = transform(sym as u8, pos, width, height);
+                *hash ^= ZOBRIST[y as usize * width as usize + x as usize][p];
+            }
+        }
+    }
+
+    let (sym, hash) = hashes[..symmetries]
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, hash)| hash)
+        .unwrap();
+    let shape = (width as u64) << 16 | (height as u64) << 8 | board.k() as u64;
+    let side = (to_move == Piece::X) as u64 | ((perspective == Piece::X) as u64) << 1;
+    (hash ^ splitmix64(shape << 2 | side), Symmetry(sym as u8))
+}
@@ -0,0 +1,169 @@
+//! The fixed-size bit set behind the board masks.
+
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Shl, Shr};
+
+/// A fixed 256-bit set. Large enough for a 15x15 board plus its padding
+/// column, and still `Copy` so boards never allocate.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct BitSet([u64; 4]);
+
+impl BitSet {
+    /// Number of bits in the set.
+    pub const CAPACITY: usize = 256;
+
+    /// The set with no bits.
+    pub const EMPTY: BitSet = BitSet([0; 4]);
+
+    /// The set holding the bits of `word`.
+    pub fn from_word(word: u64) -> Self {
+        BitSet([word, 0, 0, 0])
+    }
+
+    /// The set as a single word, if nothing above bit 63 is set.
+    pub fn as_word(&self) -> Option<u64> {
+        let [low, rest @ ..] = self.0;
+        (rest == [0; 3]).then_some(low)
+    }
+
+    /// The set holding only `index`.
+    pub fn single(index: usize) -> Self {
+        let mut bits = Self::EMPTY;
+        bits.insert(index);
+        bits
+    }
+
+    /// Sets bit `index`.
+    pub fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    /// Clears bit `index`.
+    pub fn remove(&mut self, index: usize) {
+        self.0[index / 64] &= !(1 << (index % 64));
+    }
+
+    /// Whether bit `index` is set.
+    pub fn contains(&self, index: usize) -> bool {
+        index < Self::CAPACITY && self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Whether no bit is set.
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    /// Number of bits set.
+    pub fn count_ones(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    /// Index of the lowest set bit.
+    pub fn first(&self) -> Option<usize> {
+        self.0
+            .iter()
+            .enumerate()
+            .find(|(_, &word)| word != 0)
+            .map(|(i, word)| i * 64 + word.trailing_zeros() as usize)
+    }
+
+    /// Removes and returns the lowest set bit.
+    pub fn pop_first(&mut self) -> Option<usize> {
+        let index = self.first()?;
+        self.remove(index);
+        Some(index)
+    }
+}
+
+impl BitAnd for BitSet {
+    type Output = BitSet;
+
+    fn bitand(mut self, rhs: BitSet) -> BitSet {
+        self &= rhs;
+        self
+    }
+}
+
+impl BitAndAssign for BitSet {
+    fn bitand_assign(&mut self, rhs: BitSet) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a &= b;
+        }
+    }
+}
+
+impl BitOr for BitSet {
+    type Output = BitSet;
+
+    fn bitor(mut self, rhs: BitSet) -> BitSet {
+        self |= rhs;
+        self
+    }
+}
+
+impl BitOrAssign for BitSet {
+    fn bitor_assign(&mut self, rhs: BitSet) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a |= b;
+        }
+    }
+}
+
+impl Not for BitSet {
+    type Output = BitSet;
+
+    fn not(self) -> BitSet {
+        BitSet(self.0.map(|word| !word))
+    }
+}
+
+/// Moves every bit towards index 0; bits shifted past it are dropped.
+impl Shr<usize> for BitSet {
+    type Output = BitSet;
+
+    fn shr(self, n: usize) -> BitSet {
+        let [a, b, c, d] = self.0;
+        match n {
+            0 => self,
+            // board steps are always within a word, so this is the hot path
+            1..=63 => BitSet([
+                a >> n | b << (64 - n),
+                b >> n | c << (64 - n),
+                c >> n | d << (64 - n),
+                d >> n,
+            ]),
+            _ => {
+                let mut out = [0u64; 4];
+                let (words, bits) = (n / 64, n % 64);
+                for (i, word) in out
+                    .iter_mut()
+                    .enumerate()
+                    .take(4usize.saturating_sub(words))
+                {
+                    *word = self.0[i + words] >> bits;
+                    if bits != 0 && i + words + 1 < 4 {
+                        *word |= self.0[i + words + 1] << (64 - bits);
+                    }
+                }
+                BitSet(out)
+            }
+        }
+    }
+}
+
+/// Moves every bit away from index 0; bits shifted past the top are dropped.
+impl Shl<usize> for BitSet {
+    type Output = BitSet;
+
+    fn shl(self, n: usize) -> BitSet {
+        let mut out = [0u64; 4];
+        let (words, bits) = (n / 64, n % 64);
+        for i in (words..4).rev() {
+            let src = i - words;
+            out[i] = self.0[src] << bits;
+            if bits != 0 && src > 0 {
+                out[i] |= self.0[src - 1] >> (64 - bits);
+            }
+        }
+        BitSet(out)
+    }
+}
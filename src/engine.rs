@@ -0,0 +1,394 @@
+//! A text protocol for engines, modelled on chess's UCI, so that bots
+//! written in any language can play against ours and each other. The
+//! engine reads one command per line on stdin:
+//!
+//! ```text
+//! newgame                          a new game is starting
+//! position <notation> [moves ...]  the position to think about
+//! go [movetime <ms>]               search it and answer with bestmove
+//! isready                          answered with readyok
+//! quit                             exit
+//! ```
+//!
+//! and answers `go` on stdout with
+//!
+//! ```text
+//! info score 98 pv 5 1 9
+//! bestmove 5
+//! ```
+//!
+//! The notation is `GameState::notation`, so an empty board with `o` to
+//! move means O moves first. Moves are written in each board's standard
+//! notation. Anything else the engine wants to say goes on an
+//! `info string` line.
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
+
+use crate::ai::{self, pick_best_move, Clock};
+use crate::game::{format_move, parse_move, validate_move, Board, GameState, Piece, Position};
+use crate::player::{Action, Player};
+
+/// A line sent to an engine.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// A new game is starting.
+    NewGame,
+    /// The position to think about, with the moves that led there.
+    Position(GameState),
+    /// Search the position and answer with the best move.
+    Go {
+        /// How long to think, or `None` for a full search.
+        movetime: Option<Duration>,
+    },
+    /// Answer `readyok`.
+    IsReady,
+    /// Exit.
+    Quit,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::NewGame => write!(f, "newgame"),
+            Command::Position(state) => {
+                let start = state.start();
+                write!(f, "position {}", start.notation())?;
+                if !state.moves().is_empty() {
+                    write!(f, " moves")?;
+                }
+                for pos in state.moves() {
+                    write!(f, " {}", format_move(start.board(), pos))?;
+                }
+                Ok(())
+            }
+            Command::Go { movetime: None } => write!(f, "go"),
+            Command::Go {
+                movetime: Some(movetime),
+            } => write!(f, "go movetime {}", movetime.as_millis()),
+            Command::IsReady => write!(f, "isready"),
+            Command::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match words.next() {
+            Some("newgame") => Ok(Command::NewGame),
+            Some("isready") => Ok(Command::IsReady),
+            Some("quit") => Ok(Command::Quit),
+            Some("go") => match (words.next(), words.next()) {
+                (None, _) => Ok(Command::Go { movetime: None }),
+                (Some("movetime"), Some(ms)) => match ms.parse() {
+                    Ok(ms) => Ok(Command::Go {
+                        movetime: Some(Duration::from_millis(ms)),
+                    }),
+                    Err(_) => Err(format!("bad movetime: {}", ms)),
+                },
+                _ => Err(format!("expected `go` or `go movetime <ms>`: {}", s)),
+            },
+            Some("position") => {
+                let words: Vec<&str> = words.collect();
+                let (notation, moves) = match words.iter().position(|&w| w == "moves") {
+                    Some(at) => (&words[..at], &words[at + 1..]),
+                    None => (&words[..], &[][..]),
+                };
+                let mut state: GameState = notation
+                    .join(" ")
+                    .parse()
+                    .map_err(|e| format!("bad position: {}", e))?;
+                for input in moves {
+                    parse_move(state.board(), input)
+                        .and_then(|pos| state.play(pos))
+                        .map_err(|e| format!("bad move {}: {}", input, e))?;
+                }
+                Ok(Command::Position(state))
+            }
+            _ => Err(format!("unknown command: {}", s)),
+        }
+    }
+}
+
+/// Our engine's side of the protocol, picking moves with
+/// `ai::pick_best_move`.
+pub struct Engine {
+    state: GameState,
+    rng: StdRng,
+}
+
+impl Engine {
+    /// An engine looking at an empty classic board.
+    pub fn new() -> Self {
+        Engine {
+            state: GameState::new(Board::new()),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Makes the engine's choices repeatable, as with
+    /// `ComputerPlayer::with_seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// The lines to answer `command` with.
+    pub fn respond(&mut self, command: &Command) -> Vec<String> {
+        match command {
+            Command::NewGame => {
+                self.state = GameState::new(Board::new());
+                Vec::new()
+            }
+            Command::Position(state) => {
+                self.state = state.clone();
+                Vec::new()
+            }
+            Command::Go { movetime } => self.go(*movetime),
+            Command::IsReady => vec!["readyok".to_string()],
+            Command::Quit => Vec::new(),
+        }
+    }
+
+    fn go(&mut self, movetime: Option<Duration>) -> Vec<String> {
+        let board = self.state.board();
+        let piece = self.state.to_move();
+        if self.state.result().is_over() {
+            return vec![
+                "info string the game is over".to_string(),
+                "bestmove none".to_string(),
+            ];
+        }
+
+        let (pos, score, pv) = match movetime {
+            Some(budget) => {
+                let scored = ai::score_moves_timed(board, piece, &Clock::with_budget(budget));
+                let best = scored.iter().map(|&(_, score)| score).max().unwrap();
+                let pos = scored
+                    .iter()
+                    .filter(|&&(_, score)| score == best)
+                    .choose(&mut self.rng)
+                    .unwrap()
+                    .0;
+                (pos, best, vec![pos])
+            }
+            None => {
+                let pos = pick_best_move(&mut self.rng, board, piece);
+                // the search just filled the table, so this is quick
+                match ai::analyze(board, piece).into_iter().find(|a| a.pos == pos) {
+                    Some(analysis) => (pos, analysis.score, analysis.pv),
+                    None => (pos, 0, vec![pos]),
+                }
+            }
+        };
+
+        let pv: Vec<String> = pv.iter().map(|pos| format_move(board, pos)).collect();
+        vec![
+            format!("info score {} pv {}", score, pv.join(" ")),
+            format!("bestmove {}", format_move(board, &pos)),
+        ]
+    }
+
+    /// Speaks the protocol on `input` and `output` until `quit` or the end
+    /// of the input.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let replies = match line.parse::<Command>() {
+                Ok(Command::Quit) => return Ok(()),
+                Ok(command) => self.respond(&command),
+                Err(e) => vec![format!("info string {}", e)],
+            };
+            for reply in replies {
+                writeln!(output, "{}", reply)?;
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How long an engine has to answer `isready`.
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long past its movetime an engine may take to answer `go`.
+const GRACE: Duration = Duration::from_secs(1);
+
+/// Runs another program that speaks the protocol and plays its moves.
+pub struct ExternalEngine {
+    name: String,
+    child: Child,
+    input: ChildStdin,
+    /// The engine's lines, read on their own thread so that waiting for
+    /// them can time out.
+    output: mpsc::Receiver<String>,
+    /// Thinking time per move, or `None` to leave it to the engine.
+    pub movetime: Option<Duration>,
+    /// The position the engine was last asked about.
+    last: Option<GameState>,
+}
+
+impl ExternalEngine {
+    /// Starts `command` and waits until it answers `isready`. The engine is
+    /// named after the program.
+    pub fn spawn(mut command: std::process::Command) -> io::Result<Self> {
+        let name = Path::new(command.get_program())
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .unwrap_or("Engine")
+            .to_string();
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let (lines, output) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in stdout.lines() {
+                let Ok(line) = line else { break };
+                if lines.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut engine = ExternalEngine {
+            name,
+            input: child.stdin.take().unwrap(),
+            output,
+            child,
+            movetime: None,
+            last: None,
+        };
+        engine.send(&Command::IsReady)?;
+        engine.wait_for("readyok", Some(READY_TIMEOUT))?;
+        Ok(engine)
+    }
+
+    /// Gives the engine `movetime` to think about each move.
+    pub fn with_movetime(mut self, movetime: Duration) -> Self {
+        self.movetime = Some(movetime);
+        self
+    }
+
+    fn send(&mut self, command: &Command) -> io::Result<()> {
+        writeln!(self.input, "{}", command)?;
+        self.input.flush()
+    }
+
+    /// Reads lines until one starts with `word`, and returns the rest of it.
+    /// An engine that has not got there within `timeout` is stopped.
+    fn wait_for(&mut self, word: &str, timeout: Option<Duration>) -> io::Result<String> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let line = match deadline {
+                Some(deadline) => self
+                    .output
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self.output.recv().map_err(RecvTimeoutError::from),
+            };
+            let line = match line {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("{} exited", self.name),
+                    ))
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    // a late answer would be taken for the next one, so the
+                    // engine is no use from here on
+                    let _ = self.child.kill();
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("{} did not answer {} in time", self.name, word),
+                    ));
+                }
+            };
+            let (first, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            if first == word {
+                return Ok(rest.to_string());
+            }
+        }
+    }
+
+    /// Asks the engine for its move in `state`. An answer that is not a
+    /// legal move there is an error, and so is no answer within the
+    /// movetime and a short grace period. Without a movetime the engine may
+    /// take as long as it likes.
+    pub fn best_move(&mut self, state: &GameState) -> io::Result<Position> {
+        // anything but the game carrying on from the last question is a new game
+        let carries_on = self.last.as_ref().is_some_and(|last| {
+            last.start() == state.start() && state.moves().starts_with(last.moves())
+        });
+        if !carries_on {
+            self.send(&Command::NewGame)?;
+        }
+        self.last = Some(state.clone());
+
+        self.send(&Command::Position(state.clone()))?;
+        self.send(&Command::Go {
+            movetime: self.movetime,
+        })?;
+        let answer = self.wait_for("bestmove", self.movetime.map(|movetime| movetime + GRACE))?;
+        let answer = answer.trim();
+        parse_move(state.board(), answer)
+            .and_then(|pos| validate_move(state.board(), &pos).map(|()| pos))
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} answered bestmove {}: {}", self.name, answer, e),
+                )
+            })
+    }
+}
+
+impl Player for ExternalEngine {
+    fn name(&self, piece: Piece) -> String {
+        format!("{} {}", self.name, piece)
+    }
+
+    /// The runners ask with `choose_action`, which resigns when the engine
+    /// fails; this has no way to say so, and panics instead.
+    fn choose_move(&mut self, state: &GameState) -> Position {
+        match self.best_move(state) {
+            Ok(pos) => pos,
+            Err(e) => panic!("Engine failed: {}", e),
+        }
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Action {
+        match self.best_move(state) {
+            Ok(pos) => Action::Play(pos),
+            Err(e) => Action::Resign(e.to_string()),
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        if self.send(&Command::Quit).is_err() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
@@ -1,9 +1,27 @@
-#[derive(Clone, Copy, PartialEq)]
+//! Boards, moves and the rules of the game.
+
+use crate::bitset::BitSet;
+use crate::render::Renderer;
+
+/// A player's mark. X moves first unless a game says otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Piece {
+    /// Moves first by default.
     X,
+    /// Moves second by default.
     O,
 }
 
+impl Piece {
+    /// The other piece.
+    pub fn opponent(self) -> Piece {
+        match self {
+            Piece::X => Piece::O,
+            Piece::O => Piece::X,
+        }
+    }
+}
+
 impl std::fmt::Display for Piece {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
@@ -17,89 +35,787 @@ impl std::fmt::Display for Piece {
     }
 }
 
-pub type Board = Vec<Vec<Option<Piece>>>;
+/// Largest supported side length; columns are labelled `a` to `z`.
+pub const MAX_SIDE: u8 = 26;
+
+/// An m,n,k board: `width` x `height` cells, `k` in a row to win.
+///
+/// Each piece is a bitmask with cell (x, y) at bit `y * stride + x`, where
+/// `stride = width + 1`. The extra column is always empty, so a run of bits
+/// found by shifting can never wrap from one row into the next.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Board {
+    width: u8,
+    height: u8,
+    k: u8,
+    cells: BitSet,
+    x: BitSet,
+    o: BitSet,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::with_size(3, 3, 3).unwrap()
+    }
+}
+
+impl Board {
+    /// The classic 3x3 tic-tac-toe board.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty `width` x `height` board where `k` in a row wins.
+    pub fn with_size(width: u8, height: u8, k: u8) -> Result<Self, String> {
+        if width == 0 || height == 0 || width > MAX_SIDE || height > MAX_SIDE {
+            return Err(format!(
+                "board sides must be between 1 and {}, got {}x{}",
+                MAX_SIDE, width, height
+            ));
+        }
+        if (width as usize + 1) * height as usize > BitSet::CAPACITY {
+            return Err(format!("a {}x{} board is too large", width, height));
+        }
+        if k == 0 || k > width.max(height) {
+            return Err(format!(
+                "win length must be between 1 and {}, got {}",
+                width.max(height),
+                k
+            ));
+        }
+
+        let stride = width as usize + 1;
+        let mut cells = BitSet::EMPTY;
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                cells.insert(y * stride + x);
+            }
+        }
+
+        Ok(Board {
+            width,
+            height,
+            k,
+            cells,
+            x: BitSet::EMPTY,
+            o: BitSet::EMPTY,
+        })
+    }
+
+    /// Number of columns.
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// How many in a row it takes to win.
+    pub fn k(&self) -> u8 {
+        self.k
+    }
+
+    /// Whether this is 3x3 with three in a row, the board with numpad codes.
+    pub fn is_classic(&self) -> bool {
+        self.width == 3 && self.height == 3 && self.k == 3
+    }
+
+    /// Distance in bits between vertically adjacent cells.
+    pub fn stride(&self) -> usize {
+        self.width as usize + 1
+    }
+
+    /// Whether `pos` is on the board.
+    pub fn contains(&self, pos: &Position) -> bool {
+        pos.x < self.width && pos.y < self.height
+    }
+
+    /// Bit index of `pos` in the board's masks.
+    pub fn index(&self, pos: &Position) -> usize {
+        pos.y as usize * self.stride() + pos.x as usize
+    }
+
+    /// The cell at bit `index`.
+    pub fn position(&self, index: usize) -> Position {
+        Position {
+            x: (index % self.stride()) as u8,
+            y: (index / self.stride()) as u8,
+        }
+    }
+
+    /// The piece on `pos`, if any.
+    pub fn get(&self, pos: &Position) -> Option<Piece> {
+        let index = self.index(pos);
+        if self.x.contains(index) {
+            Some(Piece::X)
+        } else if self.o.contains(index) {
+            Some(Piece::O)
+        } else {
+            None
+        }
+    }
+
+    /// Every cell on the board, excluding the padding column.
+    pub fn cells(&self) -> BitSet {
+        self.cells
+    }
+
+    /// Mask of the cells holding `piece`.
+    pub fn pieces(&self, piece: Piece) -> BitSet {
+        match piece {
+            Piece::X => self.x,
+            Piece::O => self.o,
+        }
+    }
+
+    /// Mask of the cells holding either piece.
+    pub fn occupied(&self) -> BitSet {
+        self.x | self.o
+    }
+
+    /// Mask of the empty cells.
+    pub fn empty(&self) -> BitSet {
+        self.cells & !self.occupied()
+    }
+
+    /// Iterates the cells of `mask`, which must come from this board.
+    pub fn moves(&self, mask: BitSet) -> Moves {
+        Moves {
+            mask,
+            stride: self.stride(),
+        }
+    }
+
+    /// Bit distances to the next cell along a row, a column and both diagonals.
+    pub fn directions(&self) -> [usize; 4] {
+        let stride = self.stride();
+        [1, stride, stride + 1, stride - 1]
+    }
+}
+
+/// Where a game stands.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    /// X made a line.
+    XWins,
+    /// O made a line.
+    OWins,
+    /// The board filled up without a line.
+    Draw,
+    /// Moves are still to be played.
+    Ongoing,
+}
+
+impl GameResult {
+    /// The result as it stands on `board`.
+    pub fn of(board: &Board) -> Self {
+        match is_game_won(board) {
+            Some(Piece::X) => GameResult::XWins,
+            Some(Piece::O) => GameResult::OWins,
+            None if no_more_moves(board) => GameResult::Draw,
+            None => GameResult::Ongoing,
+        }
+    }
+
+    /// The piece that won, if any.
+    pub fn winner(self) -> Option<Piece> {
+        match self {
+            GameResult::XWins => Some(Piece::X),
+            GameResult::OWins => Some(Piece::O),
+            GameResult::Draw | GameResult::Ongoing => None,
+        }
+    }
+
+    /// Whether the game has ended.
+    pub fn is_over(self) -> bool {
+        self != GameResult::Ongoing
+    }
+}
+
+/// Why a move could not be read or played.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    /// The input is not a move in this board's notation.
+    BadSyntax,
+    /// The cell is not on the board.
+    OutOfBounds,
+    /// The cell already holds a piece.
+    Occupied,
+    /// The game already has a result.
+    GameOver,
+    /// A piece tried to move when it was the other side's turn.
+    WrongTurn,
+}
+
+/// Errors from reading a move. Reading can only fail with `BadSyntax` or
+/// `OutOfBounds`.
+pub type ParseError = MoveError;
+
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MoveError::BadSyntax => write!(f, "that is not a move"),
+            MoveError::OutOfBounds => write!(f, "that cell is not on the board"),
+            MoveError::Occupied => write!(f, "that cell is already taken"),
+            MoveError::GameOver => write!(f, "the game is already over"),
+            MoveError::WrongTurn => write!(f, "it is not that side's turn"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// Why a position in notation could not be read.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PositionError {
+    /// The text is not rows of `X`, `O` and `.` joined by `/`, a side to
+    /// move and an optional win length.
+    BadSyntax,
+    /// The rows are not all the same length.
+    RaggedRows,
+    /// The board has an unsupported size or win length.
+    BadBoard(String),
+    /// One side has more than one piece more than the other.
+    BadCounts,
+    /// The side to move does not match the piece counts.
+    WrongSide,
+    /// Both sides have a line.
+    BothWon,
+    /// The side to move already has a line, so the other side moved after
+    /// the game was over.
+    PlayedAfterWin,
+}
+
+impl std::fmt::Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PositionError::BadSyntax => write!(f, "expected something like `XO./.X./..O x`"),
+            PositionError::RaggedRows => write!(f, "the rows have different lengths"),
+            PositionError::BadBoard(e) => write!(f, "{}", e),
+            PositionError::BadCounts => {
+                write!(f, "neither side can have more than one piece more")
+            }
+            PositionError::WrongSide => write!(f, "the piece counts say the other side moves"),
+            PositionError::BothWon => write!(f, "both sides have a line"),
+            PositionError::PlayedAfterWin => write!(f, "a move was played after the game was won"),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// A game in progress: the board, whose turn it is, every move played so
+/// far and the result. Moves only go through `play`, `undo` and `redo`,
+/// which keep all of these in step.
+#[derive(Clone, Debug)]
+pub struct GameState {
+    board: Board,
+    to_move: Piece,
+    moves: Vec<Position>,
+    /// Moves taken back by `undo`, the most recent last.
+    undone: Vec<Position>,
+    result: GameResult,
+}
+
+/// Moves waiting to be redone are not part of the game, so they do not
+/// count towards equality.
+impl PartialEq for GameState {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+            && self.to_move == other.to_move
+            && self.moves == other.moves
+            && self.result == other.result
+    }
+}
+
+impl Eq for GameState {}
+
+impl GameState {
+    /// A new game on `board`, X to move.
+    pub fn new(board: Board) -> Self {
+        Self::with_first(board, Piece::X)
+    }
+
+    /// A new game on `board` with `first` to move.
+    pub fn with_first(board: Board, first: Piece) -> Self {
+        GameState {
+            board,
+            to_move: first,
+            moves: Vec::new(),
+            undone: Vec::new(),
+            result: GameResult::of(&board),
+        }
+    }
+
+    /// The current position.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The piece that moves next.
+    pub fn to_move(&self) -> Piece {
+        self.to_move
+    }
+
+    /// The moves played since the game started, oldest first.
+    pub fn moves(&self) -> &[Position] {
+        &self.moves
+    }
+
+    /// Where the game stands.
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
 
-#[derive(Clone, Copy, PartialEq)]
+    /// Places the side to move's piece at `pos` and passes the turn. This
+    /// forgets any moves that `redo` could have replayed.
+    pub fn play(&mut self, pos: Position) -> Result<(), MoveError> {
+        self.play_as(self.to_move, pos)
+    }
+
+    /// Like `play`, but refuses the move unless it is `piece`'s turn.
+    pub fn play_as(&mut self, piece: Piece, pos: Position) -> Result<(), MoveError> {
+        self.place(piece, pos)?;
+        self.undone.clear();
+        Ok(())
+    }
+
+    fn place(&mut self, piece: Piece, pos: Position) -> Result<(), MoveError> {
+        if self.result.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if piece != self.to_move {
+            return Err(MoveError::WrongTurn);
+        }
+
+        apply_move(&mut self.board, &pos, piece)?;
+        self.moves.push(pos);
+        self.to_move = self.to_move.opponent();
+        self.result = GameResult::of(&self.board);
+        Ok(())
+    }
+
+    /// The position in notation: the rows from top to bottom joined by `/`,
+    /// then the side to move, then the win length unless it is 3, e.g.
+    /// `XO./.X./..O x`.
+    pub fn notation(&self) -> String {
+        let rows: Vec<String> = (0..self.board.height)
+            .map(|y| {
+                (0..self.board.width)
+                    .map(|x| match self.board.get(&Position { x, y }) {
+                        Some(Piece::X) => 'X',
+                        Some(Piece::O) => 'O',
+                        None => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        let side = match self.to_move {
+            Piece::X => 'x',
+            Piece::O => 'o',
+        };
+        if self.board.k == 3 {
+            format!("{} {}", rows.join("/"), side)
+        } else {
+            format!("{} {} {}", rows.join("/"), side, self.board.k)
+        }
+    }
+
+    /// Takes back the last move and returns it, or `None` at the start.
+    pub fn undo(&mut self) -> Option<Position> {
+        let pos = self.moves.pop()?;
+        clear_cell(&mut self.board, &pos);
+        self.to_move = self.to_move.opponent();
+        self.result = GameResult::of(&self.board);
+        self.undone.push(pos);
+        Some(pos)
+    }
+
+    /// The game as it was before its first move.
+    pub fn start(&self) -> GameState {
+        let mut start = self.clone();
+        while start.undo().is_some() {}
+        start.undone.clear();
+        start
+    }
+
+    /// Plays the last move taken back by `undo` again and returns it, or
+    /// `None` if there is none.
+    pub fn redo(&mut self) -> Option<Position> {
+        let pos = self.undone.pop()?;
+        // it was legal when it was first played, from this very position
+        self.place(self.to_move, pos).unwrap();
+        Some(pos)
+    }
+}
+
+/// Reads a position written by `GameState::notation`, whichever side moved
+/// first. The game starts there, with no moves to undo.
+impl std::str::FromStr for GameState {
+    type Err = PositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let (rows, side, k) = match fields[..] {
+            [rows, side] => (rows, side, 3),
+            [rows, side, k] => (rows, side, k.parse().map_err(|_| PositionError::BadSyntax)?),
+            _ => return Err(PositionError::BadSyntax),
+        };
+        let to_move = match side {
+            "x" | "X" => Piece::X,
+            "o" | "O" => Piece::O,
+            _ => return Err(PositionError::BadSyntax),
+        };
+
+        let rows: Vec<&str> = rows.split('/').collect();
+        let width = rows[0].chars().count();
+        if rows.iter().any(|row| row.chars().count() != width) {
+            return Err(PositionError::RaggedRows);
+        }
+        let size = |n: usize| u8::try_from(n).unwrap_or(u8::MAX);
+        let mut board =
+            Board::with_size(size(width), size(rows.len()), k).map_err(PositionError::BadBoard)?;
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                let pos = Position {
+                    x: x as u8,
+                    y: y as u8,
+                };
+                match cell {
+                    'X' | 'x' => place_piece(&mut board, &pos, Piece::X),
+                    'O' | 'o' => place_piece(&mut board, &pos, Piece::O),
+                    '.' => {}
+                    _ => return Err(PositionError::BadSyntax),
+                }
+            }
+        }
+
+        // either side may have moved first, so with equal counts either can
+        // be to move, and otherwise it is the side with fewer pieces
+        let xs = board.x.count_ones();
+        let os = board.o.count_ones();
+        let expected = if xs == os {
+            to_move
+        } else if xs == os + 1 {
+            Piece::O
+        } else if os == xs + 1 {
+            Piece::X
+        } else {
+            return Err(PositionError::BadCounts);
+        };
+        if to_move != expected {
+            return Err(PositionError::WrongSide);
+        }
+        let lines = |piece| {
+            board
+                .directions()
+                .iter()
+                .any(|&step| !runs(board.pieces(piece), step, board.k).is_empty())
+        };
+        if lines(Piece::X) && lines(Piece::O) {
+            return Err(PositionError::BothWon);
+        }
+        if lines(to_move) {
+            return Err(PositionError::PlayedAfterWin);
+        }
+
+        Ok(GameState {
+            board,
+            to_move,
+            moves: Vec::new(),
+            undone: Vec::new(),
+            result: GameResult::of(&board),
+        })
+    }
+}
+
+/// Start cells of every run of `len` set bits spaced `step` apart.
+pub fn runs(mask: BitSet, step: usize, len: u8) -> BitSet {
+    if let Some(word) = mask.as_word() {
+        let mut run = word;
+        for _ in 1..len {
+            run &= run >> step;
+        }
+        return BitSet::from_word(run);
+    }
+
+    let mut run = mask;
+    for _ in 1..len {
+        run &= run >> step;
+    }
+    run
+}
+
+/// A cell, counted from the top-left corner.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Position {
+    /// Column, left to right.
     pub x: u8,
+    /// Row, top to bottom.
     pub y: u8,
 }
+
+/// The cell's numpad code if it is on the classic board. Code that knows
+/// the board and the scheme in use writes cells with `Scheme::format`.
 impl std::fmt::Display for Position {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let pos = position_to_move_code(self)
-            .unwrap_or_else(|| panic!("Invalid position: ({}, {})", self.x, self.y));
-        write!(f, "{}", pos)
+        match position_to_move_code(self) {
+            Some(code) => write!(f, "{}", code),
+            // only the 3x3 cells have numpad codes
+            None => write!(f, "({}, {})", self.x, self.y),
+        }
+    }
+}
+
+/// Iterates the cells of a mask as positions, lowest bit first.
+#[derive(Clone, Copy)]
+pub struct Moves {
+    mask: BitSet,
+    stride: usize,
+}
+
+impl Iterator for Moves {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Position> {
+        let index = self.mask.pop_first()?;
+        Some(Position {
+            x: (index % self.stride) as u8,
+            y: (index / self.stride) as u8,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.mask.count_ones() as usize;
+        (n, Some(n))
     }
 }
 
+impl ExactSizeIterator for Moves {}
+
+/// Whether someone has won or the board is full.
 pub fn is_game_over(board: &Board) -> bool {
     is_game_won(board).is_some() || no_more_moves(board)
 }
 
+/// Whether the board is full.
 pub fn no_more_moves(board: &Board) -> bool {
-    board
-        .iter()
-        .all(|row| row.iter().all(|cell| cell.is_some()))
+    board.empty().is_empty()
 }
 
+/// The piece with `k` in a row, if any.
 pub fn is_game_won(board: &Board) -> Option<Piece> {
-    // rows and columns
-    for i in 0..3 {
-        if let Some(piece) = check_line(&[board[i][0], board[i][1], board[i][2]]) {
-            return Some(piece);
-        }
-        if let Some(piece) = check_line(&[board[0][i], board[1][i], board[2][i]]) {
-            return Some(piece);
+    [Piece::X, Piece::O].into_iter().find(|&piece| {
+        let pieces = board.pieces(piece);
+        pieces.count_ones() >= board.k as u32
+            && board
+                .directions()
+                .into_iter()
+                .any(|step| !runs(pieces, step, board.k).is_empty())
+    })
+}
+
+/// The cells of a line of `k` for the winner, if someone has won.
+pub fn winning_line(board: &Board) -> Option<Vec<Position>> {
+    let piece = is_game_won(board)?;
+    board.directions().into_iter().find_map(|step| {
+        // a run is found at its first cell
+        let start = runs(board.pieces(piece), step, board.k).first()?;
+        Some(
+            (0..board.k as usize)
+                .map(|i| board.position(start + i * step))
+                .collect(),
+        )
+    })
+}
+
+/// Ways of naming cells. All of them read any board size; the numbered
+/// schemes just need more digits on larger boards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scheme {
+    /// Numbered like a numeric keypad: 1 at the bottom-left, counting along
+    /// each row and then up, so `7` is the top-left of the classic board.
+    Numpad,
+    /// Numbered like a phone keypad: 1 at the top-left, counting along each
+    /// row and then down.
+    Phone,
+    /// A column letter and a row number counted from the bottom, so `a1` is
+    /// the bottom-left cell.
+    Chess,
+    /// The row and the column counted from the top-left, e.g. `1,3` for
+    /// the top-right of the classic board.
+    RowCol,
+}
+
+impl Scheme {
+    /// The scheme `parse_move` and `format_move` use: the numpad on the
+    /// classic board, chess coordinates otherwise.
+    pub fn standard(board: &Board) -> Scheme {
+        if board.is_classic() {
+            Scheme::Numpad
+        } else {
+            Scheme::Chess
         }
     }
 
-    // diagonals
-    if let Some(piece) = check_line(&[board[0][0], board[1][1], board[2][2]]) {
-        return Some(piece);
+    /// Reads a cell of `board` written in this scheme.
+    pub fn parse(self, board: &Board, input: &str) -> Result<Position, ParseError> {
+        let input = input.trim();
+        let pos = match self {
+            Scheme::Numpad if board.is_classic() => return move_code_to_position(input),
+            Scheme::Numpad | Scheme::Phone => {
+                let n = input.parse::<usize>().map_err(|_| MoveError::BadSyntax)?;
+                let width = board.width as usize;
+                if n == 0 || n > width * board.height as usize {
+                    return Err(MoveError::OutOfBounds);
+                }
+                let (x, row) = ((n - 1) % width, (n - 1) / width);
+                let y = match self {
+                    Scheme::Numpad => board.height as usize - 1 - row,
+                    _ => row,
+                };
+                Position {
+                    x: x as u8,
+                    y: y as u8,
+                }
+            }
+            Scheme::Chess => {
+                let mut chars = input.chars();
+                let column = match chars.next() {
+                    Some(c) if c.is_ascii_alphabetic() => c.to_ascii_lowercase(),
+                    _ => return Err(MoveError::BadSyntax),
+                };
+                let row = chars
+                    .as_str()
+                    .parse::<u8>()
+                    .map_err(|_| MoveError::BadSyntax)?;
+                if row == 0 || row > board.height {
+                    return Err(MoveError::OutOfBounds);
+                }
+                Position {
+                    x: column as u8 - b'a',
+                    y: board.height - row,
+                }
+            }
+            Scheme::RowCol => {
+                let (row, column) = input.split_once(',').ok_or(MoveError::BadSyntax)?;
+                let number = |s: &str| s.trim().parse::<u8>().map_err(|_| MoveError::BadSyntax);
+                let (row, column) = (number(row)?, number(column)?);
+                if row == 0 || column == 0 {
+                    return Err(MoveError::OutOfBounds);
+                }
+                Position {
+                    x: column - 1,
+                    y: row - 1,
+                }
+            }
+        };
+        if board.contains(&pos) {
+            Ok(pos)
+        } else {
+            Err(MoveError::OutOfBounds)
+        }
     }
-    if let Some(piece) = check_line(&[board[0][2], board[1][1], board[2][0]]) {
-        return Some(piece);
+
+    /// Writes `pos` the way `parse` reads it. Cells off the board have no
+    /// name, and are written as their coordinates instead.
+    pub fn format(self, board: &Board, pos: &Position) -> String {
+        if !board.contains(pos) {
+            return format!("({}, {})", pos.x, pos.y);
+        }
+        let (x, y) = (pos.x as usize, pos.y as usize);
+        let width = board.width as usize;
+        match self {
+            Scheme::Numpad => ((board.height as usize - 1 - y) * width + x + 1).to_string(),
+            Scheme::Phone => (y * width + x + 1).to_string(),
+            Scheme::Chess => format!("{}{}", (b'a' + pos.x) as char, board.height - pos.y),
+            Scheme::RowCol => format!("{},{}", y + 1, x + 1),
+        }
     }
 
-    None
+    /// The range of moves on `board`, for prompts, e.g. `[1..9]`.
+    pub fn describe(self, board: &Board) -> String {
+        let corners = match self {
+            // numbers count up from the bottom-left
+            Scheme::Numpad | Scheme::Chess => (
+                Position {
+                    x: 0,
+                    y: board.height - 1,
+                },
+                Position {
+                    x: board.width - 1,
+                    y: 0,
+                },
+            ),
+            Scheme::Phone | Scheme::RowCol => (
+                Position { x: 0, y: 0 },
+                Position {
+                    x: board.width - 1,
+                    y: board.height - 1,
+                },
+            ),
+        };
+        format!(
+            "[{}..{}]",
+            self.format(board, &corners.0),
+            self.format(board, &corners.1)
+        )
+    }
 }
 
-pub fn check_line(line: &[Option<Piece>]) -> Option<Piece> {
-    if line[0] == line[1] && line[1] == line[2] {
-        line[0]
-    } else {
-        None
+impl std::str::FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "numpad" => Ok(Scheme::Numpad),
+            "phone" => Ok(Scheme::Phone),
+            "chess" => Ok(Scheme::Chess),
+            "rowcol" => Ok(Scheme::RowCol),
+            _ => Err(format!("unknown input scheme: {}", s)),
+        }
     }
 }
 
-pub fn move_code_to_position(input: &str) -> Option<Position> {
+/// Parses a move in the board's standard scheme: numpad digits on the
+/// classic board, otherwise a column letter and a row number counted from
+/// the bottom (`a1` is the bottom-left cell, like the numpad's `1`).
+pub fn parse_move(board: &Board, input: &str) -> Result<Position, ParseError> {
+    Scheme::standard(board).parse(board, input)
+}
+
+/// Formats a move the way `parse_move` reads it.
+pub fn format_move(board: &Board, pos: &Position) -> String {
+    Scheme::standard(board).format(board, pos)
+}
+
+/// Reads a numpad digit as a 3x3 cell: `7` is top-left, `3` bottom-right.
+pub fn move_code_to_position(input: &str) -> Result<Position, ParseError> {
     if input.len() != 1 {
-        return None;
+        return Err(MoveError::BadSyntax);
     }
 
-    let num = match input.parse::<u8>() {
-        Ok(n) => n,
-        Err(_) => {
-            println!("Invalid input, try again.");
-            return None;
-        }
-    };
+    let num = input.parse::<u8>().map_err(|_| MoveError::BadSyntax)?;
 
     // its a numpad, retard
     match num {
-        7 => Some(Position { x: 0, y: 0 }),
-        8 => Some(Position { x: 1, y: 0 }),
-        9 => Some(Position { x: 2, y: 0 }),
-        4 => Some(Position { x: 0, y: 1 }),
-        5 => Some(Position { x: 1, y: 1 }),
-        6 => Some(Position { x: 2, y: 1 }),
-        1 => Some(Position { x: 0, y: 2 }),
-        2 => Some(Position { x: 1, y: 2 }),
-        3 => Some(Position { x: 2, y: 2 }),
-        _ => None,
+        7 => Ok(Position { x: 0, y: 0 }),
+        8 => Ok(Position { x: 1, y: 0 }),
+        9 => Ok(Position { x: 2, y: 0 }),
+        4 => Ok(Position { x: 0, y: 1 }),
+        5 => Ok(Position { x: 1, y: 1 }),
+        6 => Ok(Position { x: 2, y: 1 }),
+        1 => Ok(Position { x: 0, y: 2 }),
+        2 => Ok(Position { x: 1, y: 2 }),
+        3 => Ok(Position { x: 2, y: 2 }),
+        _ => Err(MoveError::OutOfBounds),
     }
 }
 
+/// The numpad digit for a 3x3 cell.
 pub fn position_to_move_code(pos: &Position) -> Option<u8> {
     match (pos.x, pos.y) {
         (0, 0) => Some(7),
@@ -115,45 +831,56 @@ pub fn position_to_move_code(pos: &Position) -> Option<u8> {
     }
 }
 
+/// Checks that `pos` is an empty cell on the board.
+pub fn validate_move(board: &Board, pos: &Position) -> Result<(), MoveError> {
+    if !board.contains(pos) {
+        Err(MoveError::OutOfBounds)
+    } else if board.get(pos).is_some() {
+        Err(MoveError::Occupied)
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether `pos` is an empty cell on the board.
 pub fn is_valid_move(board: &Board, pos: &Position) -> bool {
-    pos.x < 3 && pos.y < 3 && board[pos.y as usize][pos.x as usize].is_none()
+    validate_move(board, pos).is_ok()
 }
 
-pub fn apply_move(board: &mut Board, pos: &Position, piece: Piece) {
-    board[pos.y as usize][pos.x as usize] = Some(piece);
+/// Puts `piece` on `pos` if it is an empty cell on the board.
+pub fn apply_move(board: &mut Board, pos: &Position, piece: Piece) -> Result<(), MoveError> {
+    validate_move(board, pos)?;
+    place_piece(board, pos, piece);
+    Ok(())
 }
 
-pub fn display_board(board: &Board) {
-    for (y, row) in board.iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            match cell {
-                Some(piece) => print!("{}", piece),
-                None => {
-                    let pos = position_to_move_code(&Position {
-                        x: x as u8,
-                        y: y as u8,
-                    })
-                    .unwrap_or_else(|| panic!("Invalid position: ({}, {})", x, y));
+/// Puts `piece` on `pos` without any checks, for callers such as the search
+/// that only ever generate empty cells.
+pub fn place_piece(board: &mut Board, pos: &Position, piece: Piece) {
+    let index = board.index(pos);
+    match piece {
+        Piece::X => board.x.insert(index),
+        Piece::O => board.o.insert(index),
+    }
+}
 
-                    print!("{}", pos);
-                }
-            }
-        }
-        println!();
+/// Removes whatever piece is on `pos`.
+pub fn clear_cell(board: &mut Board, pos: &Position) {
+    let index = board.index(pos);
+    board.x.remove(index);
+    board.o.remove(index);
+}
+
+/// The plain style of `render::Renderer`: one character per cell, the
+/// numpad codes of empty cells on the classic board and coordinates around
+/// larger ones.
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", Renderer::PLAIN.render(self))
     }
 }
 
-pub fn get_available_moves(board: &Board) -> Vec<Position> {
-    let mut valid_moves = Vec::new();
-    (0..3).for_each(|y| {
-        (0..3).for_each(|x| {
-            if board[y][x].is_none() {
-                valid_moves.push(Position {
-                    x: x as u8,
-                    y: y as u8,
-                });
-            }
-        });
-    });
-    valid_moves
+/// The empty cells of the board. Does not allocate.
+pub fn get_available_moves(board: &Board) -> Moves {
+    board.moves(board.empty())
 }
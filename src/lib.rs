@@ -0,0 +1,29 @@
+//! Tic-tac-toe and its m,n,k generalizations: the rules, a minimax solver
+//! and the players that drive a game.
+//!
+//! ```
+//! use ppttt::game::{Board, GameState};
+//! use ppttt::player::{run_game, ComputerPlayer};
+//!
+//! let game = run_game(
+//!     GameState::new(Board::new()),
+//!     &mut ComputerPlayer::minimax(),
+//!     &mut ComputerPlayer::minimax(),
+//!     None,
+//! ).unwrap();
+//! // perfect play from both sides always draws
+//! assert_eq!(game.result().winner(), None);
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod ai;
+pub mod bitset;
+pub mod engine;
+pub mod game;
+pub mod net;
+pub mod player;
+pub mod record;
+pub mod render;
+pub mod series;
+pub mod tui;
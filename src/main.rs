@@ -1,196 +1,927 @@
-use core::time;
-use std::os::unix::raw::time_t;
-
-use clap::{arg, command, ArgAction};
-use game::Piece;
+use clap::{
+    arg,
+    builder::{PossibleValuesParser, TypedValueParser},
+    command,
+    error::ErrorKind,
+    value_parser, Arg, ArgAction, ArgMatches, Command,
+};
 use indicatif::ProgressIterator;
-use rand::{seq::SliceRandom, Rng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::io::IsTerminal;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::{
-    ai::{pick_best_move, pick_best_move_par},
-    game::{
-        apply_move, display_board, get_available_moves, is_game_won, is_valid_move,
-        move_code_to_position, no_more_moves,
+use ppttt::{
+    ai,
+    ai::{
+        mcts::{Budget, DEFAULT_EXPLORATION},
+        pick_best_move, pick_best_move_par,
+        solve::Value,
+        Difficulty, Selection, Strategy,
+    },
+    engine::{Engine, ExternalEngine},
+    game::{Board, GameState, Piece, Scheme},
+    net,
+    player::{
+        display_analysis, display_board, run_game, ComputerPlayer, Forfeit, HumanPlayer, Player,
     },
+    record::GameRecord,
+    render::{ColorChoice, Renderer, Style},
+    series::{Format, Series},
+    tui,
 };
 
-mod ai;
-mod game;
-
-#[cfg(test)]
-mod test;
+fn main() {
+    let matches = cli().get_matches();
+    let renderer = Renderer {
+        style: *matches.get_one::<Style>("style").unwrap(),
+        color: matches.get_one::<ColorChoice>("color").unwrap().enabled(),
+        scheme: matches
+            .get_one::<Option<Scheme>>("input")
+            .copied()
+            .flatten(),
+    };
 
-enum GameMode {
-    PlayerVsPlayer,
-    PlayerVsComputer,
+    match matches.subcommand() {
+        Some(("play", matches)) => do_play(matches, renderer),
+        Some(("bench", matches)) => do_performance_check(*matches.get_one::<u32>("games").unwrap()),
+        Some(("analyze", matches)) => do_analysis(
+            start_position(matches),
+            matches.get_one::<String>("POSITION").unwrap(),
+            &renderer,
+        ),
+        Some(("solve", matches)) => do_solve(
+            start_position(matches),
+            matches.get_one::<PathBuf>("output").map(PathBuf::as_path),
+            &renderer,
+        ),
+        Some(("selfplay", matches)) => do_selfplay(matches),
+        Some(("engine", matches)) => do_engine(matches.get_one::<u64>("seed").copied()),
+        Some(("replay", matches)) => {
+            do_replay(matches.get_one::<PathBuf>("FILE").unwrap(), &renderer)
+        }
+        Some(("serve", matches)) => do_serve(
+            start_position(matches),
+            *matches.get_one::<u16>("port").unwrap(),
+        ),
+        Some(("connect", matches)) => {
+            do_connect(matches.get_one::<String>("ADDR").unwrap(), &renderer)
+        }
+        // a bare `ppttt` plays a game with the defaults
+        _ => do_play(
+            cli()
+                .get_matches_from(["ppttt", "play"])
+                .subcommand_matches("play")
+                .unwrap(),
+            renderer,
+        ),
+    }
 }
 
-fn main() {
-    let matches = command!() // Automatically sets up the command with metadata from Cargo.toml
+fn cli() -> Command {
+    command!() // Automatically sets up the command with metadata from Cargo.toml
         .about("Plays a game of Tic Tac Toe")
         .arg(
-            arg!(-p --pvp "Enables Player vs Player mode").action(ArgAction::SetTrue), // Explicitly set the action
+            arg!(--style <STYLE> "How boards are drawn: one character per cell, or a grid")
+                .value_parser(
+                    PossibleValuesParser::new(["plain", "ascii", "unicode"])
+                        .map(|style| style.parse::<Style>().unwrap()),
+                )
+                .default_value("plain")
+                .global(true),
         )
         .arg(
-            arg!(-c --pvc "Enables Player vs Computer mode")
-                .default_value("true")
-                .action(ArgAction::SetTrue), // Explicitly set the action
+            arg!(--input <SCHEME> "How cells are named: numpad (7 top-left), phone (1 top-left), chess (a1 bottom-left) or row,col [default: numpad on 3x3, chess otherwise]")
+                .value_parser(
+                    PossibleValuesParser::new(["auto", "numpad", "phone", "chess", "rowcol"])
+                        .map(|scheme| match scheme.as_str() {
+                            "auto" => None,
+                            scheme => Some(scheme.parse::<Scheme>().unwrap()),
+                        }),
+                )
+                .default_value("auto")
+                .hide_default_value(true)
+                .global(true),
         )
         .arg(
-            arg!(-k --performance "Reports the time taken to make a move.")
-                .action(ArgAction::SetTrue), // Explicitly set the action
+            arg!(--color <WHEN> "Colors the pieces and the winning line")
+                .value_parser(
+                    PossibleValuesParser::new(["auto", "always", "never"])
+                        .map(|when| when.parse::<ColorChoice>().unwrap()),
+                )
+                .default_value("auto")
+                .global(true),
+        )
+        .subcommand(
+            Command::new("play")
+                .about("Plays a game against the computer or another person [default]")
+                .arg(
+                    arg!(--mode <MODE> "Who plays: a person against the computer, or two people")
+                        .value_parser(["pvc", "pvp"])
+                        .default_value("pvc"),
+                )
+                .arg(
+                    arg!(--side <SIDE> "The piece the human plays against the computer [default: random]")
+                        .value_parser(["x", "o"]),
+                )
+                .arg(
+                    arg!(--first <WHO> "Who moves first against the computer, whichever side they play [default: random]")
+                        .value_parser(["human", "computer"]),
+                )
+                .arg(
+                    arg!(--seed <SEED> "Seeds the random choices, so a game can be set up again [default: random]")
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(
+                    arg!(--"best-of" <N> "Plays a match of N games, swapping sides after each")
+                        .value_parser(value_parser!(u32).range(1..)),
+                )
+                .arg(
+                    arg!(--"first-to" <N> "Plays a match until someone has won N games, swapping sides after each")
+                        .value_parser(value_parser!(u32).range(1..))
+                        .conflicts_with("best-of"),
+                )
+                .arg(
+                    arg!(--tui "Plays on the full screen, picking cells with the arrow keys or the mouse")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--"no-undo" "Turns off undo and redo, e.g. for rated games")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(save_dir_arg())
+                .args(board_args())
+                .args(engine_args()),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Reports the time taken to make a move")
+                .arg(
+                    arg!(--games <N> "Searches to time for each measurement")
+                        .value_parser(value_parser!(u32).range(1..))
+                        .default_value("1000"),
+                ),
         )
-        .get_matches();
+        .subcommand(
+            Command::new("analyze")
+                .about("Shows what the engine thinks of every move in a position")
+                .arg(arg!(<POSITION> "A position like \"XO./.X./..O x\", or the moves played so far, e.g. \"5 1 9\""))
+                .args(board_args()),
+        )
+        .subcommand(
+            Command::new("solve")
+                .about("Solves every position reachable from the start")
+                .arg(
+                    arg!(-o --output <FILE> "Saves the solution table to this file")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .args(board_args()),
+        )
+        .subcommand(
+            Command::new("selfplay")
+                .about("Plays the computer against itself and counts the results")
+                .arg(
+                    arg!(--games <N> "Number of games to play")
+                        .value_parser(value_parser!(u32).range(1..))
+                        .default_value("100"),
+                )
+                .arg(save_dir_arg())
+                .arg(arg!(--x <PROGRAM> "Plays X with an external engine, e.g. \"./bot --fast\""))
+                .arg(arg!(--o <PROGRAM> "Plays O with an external engine"))
+                .args(board_args())
+                .args(engine_args()),
+        )
+        .subcommand(
+            Command::new("engine")
+                .about("Speaks the engine protocol on stdin and stdout, for other programs to play against")
+                .arg(
+                    arg!(--seed <SEED> "Seeds the engine's choices among equally good moves")
+                        .value_parser(value_parser!(u64)),
+                ),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Steps through a saved game move by move")
+                .arg(arg!(<FILE> "A game record").value_parser(value_parser!(PathBuf))),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Hosts games over the network, pairing players as they connect")
+                .arg(
+                    arg!(--port <PORT> "Port to listen on")
+                        .value_parser(value_parser!(u16))
+                        .default_value("7878"),
+                )
+                .args(board_args()),
+        )
+        .subcommand(
+            Command::new("connect")
+                .about("Plays a game on a server started with `serve`")
+                .arg(arg!(<ADDR> "The server, e.g. localhost:7878")),
+        )
+}
+
+/// The options that set up the board, shared by every subcommand that
+/// needs one.
+fn board_args() -> [Arg; 4] {
+    [
+        arg!(--width <WIDTH> "Number of columns on the board")
+            .value_parser(value_parser!(u8))
+            .default_value("3"),
+        arg!(--height <HEIGHT> "Number of rows on the board")
+            .value_parser(value_parser!(u8))
+            .default_value("3"),
+        arg!(--win <K> "How many in a row it takes to win")
+            .value_parser(value_parser!(u8))
+            .default_value("3"),
+        arg!(--position <POSITION> "Starts from a position like \"XO./.X./..O x\" instead of an empty board")
+            .value_parser(value_parser!(GameState)),
+    ]
+}
+
+/// The options that decide how the computer plays.
+fn engine_args() -> [Arg; 7] {
+    [
+        arg!(-d --difficulty <LEVEL> "How well the computer plays")
+            .value_parser(["easy", "medium", "hard", "perfect"])
+            .default_value("perfect"),
+        arg!(--temperature <T> "Varies the computer's play among moves with the best outcome")
+            .value_parser(|t: &str| match t.parse::<f64>() {
+                Ok(t) if t.is_finite() && t > 0.0 => Ok(t),
+                _ => Err("expected a number greater than 0".to_string()),
+            }),
+        arg!(--engine <ENGINE> "How the computer searches for its moves")
+            .value_parser(["minimax", "mcts"])
+            .default_value("minimax"),
+        arg!(--iterations <N> "Playouts per move for the mcts engine")
+            .value_parser(value_parser!(u32))
+            .default_value("20000"),
+        arg!(--movetime <MS> "Thinking time per move in milliseconds, instead of a full search or --iterations")
+            .value_parser(value_parser!(u64)),
+        arg!(--exploration <C> "Exploration constant for the mcts engine [default: sqrt(2)]")
+            .value_parser(value_parser!(f64)),
+        arg!(--trees <N> "Independent trees the mcts engine grows in parallel, sharing --iterations")
+            .value_parser(value_parser!(u32).range(1..))
+            .default_value("1"),
+    ]
+}
 
-    // perform a performance check
-    if *matches.get_one::<bool>("performance").unwrap() {
-        do_performance_check();
+fn save_dir_arg() -> Arg {
+    arg!(--"save-dir" <DIR> "Saves every finished game to a record in this directory")
+        .value_parser(value_parser!(PathBuf))
+}
+
+/// The position given by `board_args`.
+fn start_position(matches: &ArgMatches) -> GameState {
+    if let Some(state) = matches.get_one::<GameState>("position") {
+        return state.clone();
+    }
+    match Board::with_size(
+        *matches.get_one::<u8>("width").unwrap(),
+        *matches.get_one::<u8>("height").unwrap(),
+        *matches.get_one::<u8>("win").unwrap(),
+    ) {
+        Ok(board) => GameState::new(board),
+        Err(e) => {
+            eprintln!("Invalid board: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// The computer player described by `engine_args`.
+fn computer_from(matches: &ArgMatches) -> ComputerPlayer {
+    let selection = match matches.get_one::<f64>("temperature") {
+        Some(&temperature) => Selection::Softmax { temperature },
+        None => Selection::Best,
+    };
+
+    let strategy = match matches.get_one::<String>("engine").unwrap().as_str() {
+        "mcts" => Strategy::Mcts {
+            budget: match matches.get_one::<u64>("movetime") {
+                Some(&ms) => Budget::Time(Duration::from_millis(ms)),
+                None => Budget::Iterations(*matches.get_one::<u32>("iterations").unwrap()),
+            },
+            exploration: matches
+                .get_one::<f64>("exploration")
+                .copied()
+                .unwrap_or(DEFAULT_EXPLORATION),
+            trees: *matches.get_one::<u32>("trees").unwrap(),
+        },
+        _ if matches.contains_id("movetime") => Strategy::Deepening {
+            budget: Duration::from_millis(*matches.get_one::<u64>("movetime").unwrap()),
+        },
+        _ => matches
+            .get_one::<String>("difficulty")
+            .unwrap()
+            .parse::<Difficulty>()
+            .unwrap()
+            .strategy(),
+    };
+    ComputerPlayer::new(strategy, selection)
+}
+
+fn do_play(matches: &ArgMatches, renderer: Renderer) {
+    let start = start_position(matches);
+    let mut side = matches
+        .get_one::<String>("side")
+        .map(|side| parse_side(side));
+    let mut first = matches
+        .get_one::<String>("first")
+        .map(|first| parse_first(first));
+    let mut seed = matches.get_one::<u64>("seed").copied();
+    let format = match (
+        matches.get_one::<u32>("best-of"),
+        matches.get_one::<u32>("first-to"),
+    ) {
+        (_, Some(&n)) => Format::FirstTo(n),
+        (Some(&n), None) => Format::BestOf(n),
+        (None, None) => Format::BestOf(1),
+    };
+    let settings = MatchSettings {
+        format,
+        allow_undo: !*matches.get_one::<bool>("no-undo").unwrap(),
+        tui: *matches.get_one::<bool>("tui").unwrap(),
+        save_dir: matches.get_one::<PathBuf>("save-dir").cloned(),
+        renderer,
+    };
+    if settings.tui && !std::io::stdout().is_terminal() {
+        cli()
+            .error(ErrorKind::InvalidValue, "--tui needs a terminal")
+            .exit();
+    }
+
+    if matches.get_one::<String>("mode").unwrap() == "pvp" {
+        if side.is_some() || first.is_some() || seed.is_some() {
+            cli()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--side, --first and --seed only apply to --mode pvc",
+                )
+                .exit();
+        }
+        play_pvp(start, &settings);
         return;
     }
 
-    let game_mode = if *matches.get_one::<bool>("pvp").unwrap() {
-        GameMode::PlayerVsPlayer
+    // a position already says whose turn it is, an empty board lets either
+    // side start
+    let fixed_first = !start.board().occupied().is_empty();
+    if fixed_first {
+        if let (Some(side), Some(first)) = (side, first) {
+            if (side == start.to_move()) != (first == Turn::Player) {
+                cli()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        format!(
+                            "{} moves next in this position, so --side {} cannot go with --first {}",
+                            start.to_move(),
+                            side.to_string().to_lowercase(),
+                            matches.get_one::<String>("first").unwrap()
+                        ),
+                    )
+                    .exit();
+            }
+        }
+    }
+
+    // ask for whatever the command line left open
+    if std::io::stdin().is_terminal() {
+        if side.is_none() && !(fixed_first && first.is_some()) {
+            side = ask("Play as", &["x", "o"]).map(|side| parse_side(&side));
+        }
+        if first.is_none() && !(fixed_first && side.is_some()) {
+            first = ask("Who moves first", &["human", "computer"]).map(|first| parse_first(&first));
+        }
+        if seed.is_none() {
+            seed = ask_seed();
+        }
+    }
+
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    println!("Seed: {}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let (human, state) = if fixed_first {
+        let to_move = start.to_move();
+        let human = match (side, first) {
+            (Some(side), _) => side,
+            (None, Some(Turn::Player)) => to_move,
+            (None, Some(Turn::Computer)) => to_move.opponent(),
+            (None, None) => *[Piece::X, Piece::O].choose(&mut rng).unwrap(),
+        };
+        (human, start)
     } else {
-        GameMode::PlayerVsComputer
+        let human = side.unwrap_or_else(|| *[Piece::X, Piece::O].choose(&mut rng).unwrap());
+        let first =
+            first.unwrap_or_else(|| *[Turn::Player, Turn::Computer].choose(&mut rng).unwrap());
+        let first_piece = match first {
+            Turn::Player => human,
+            Turn::Computer => human.opponent(),
+        };
+        (human, GameState::with_first(*start.board(), first_piece))
     };
 
-    match game_mode {
-        GameMode::PlayerVsPlayer => play_pvp(),
-        GameMode::PlayerVsComputer => play_pvc(),
+    let computer = computer_from(matches).with_seed(rng.gen());
+    play_pvc(state, computer, human, &settings);
+}
+
+fn parse_side(side: &str) -> Piece {
+    match side {
+        "x" => Piece::X,
+        _ => Piece::O,
     }
 }
 
-pub fn play_pvp() {
-    let mut board = vec![vec![None; 3]; 3];
-    let mut current_piece = Piece::X;
+fn parse_first(first: &str) -> Turn {
+    match first {
+        "human" => Turn::Player,
+        _ => Turn::Computer,
+    }
+}
 
+/// Asks `question` until the answer is one of `choices`. An empty answer,
+/// or the end of input, leaves it to chance.
+fn ask(question: &str, choices: &[&str]) -> Option<String> {
     loop {
-        display_board(&board);
-        println!("Player {}, enter your move [1..9]:", current_piece);
-
+        println!("{}? [{}, Enter for random]", question, choices.join("/"));
         let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-
-        let pos = match move_code_to_position(input) {
-            Some(p) => p,
-            None => {
-                println!("Invalid input, try again.");
-                continue;
-            }
-        };
-
-        if !is_valid_move(&board, &pos) {
-            println!("Invalid move, try again.");
-            continue;
+        if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+            return None;
+        }
+        let answer = input.trim().to_lowercase();
+        if answer.is_empty() {
+            return None;
+        }
+        // accept any unambiguous start, e.g. "c" for computer
+        match choices.iter().find(|choice| choice.starts_with(&answer)) {
+            Some(choice) => return Some(choice.to_string()),
+            None => println!("Please answer {}.", choices.join(" or ")),
         }
+    }
+}
 
-        apply_move(&mut board, &pos, current_piece);
-        if let Some(winner) = is_game_won(&board) {
-            display_board(&board);
-            println!("Player {} wins!", winner);
-            break;
+fn ask_seed() -> Option<u64> {
+    loop {
+        println!("Seed? [a number, Enter for random]");
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+            return None;
         }
-        if no_more_moves(&board) {
-            display_board(&board);
-            println!("Game over! It's a draw!");
-            break;
+        match input.trim() {
+            "" => return None,
+            seed => match seed.parse() {
+                Ok(seed) => return Some(seed),
+                Err(_) => println!("Please enter a whole number."),
+            },
         }
-        current_piece = if current_piece == Piece::X {
-            Piece::O
-        } else {
-            Piece::X
-        };
     }
 }
 
+/// How the games of a `play` run are played.
+pub struct MatchSettings {
+    pub format: Format,
+    pub allow_undo: bool,
+    /// Plays on the full screen instead of line by line.
+    pub tui: bool,
+    pub save_dir: Option<PathBuf>,
+    /// Draws the boards and names the cells.
+    pub renderer: Renderer,
+}
+
+pub fn play_pvp(state: GameState, settings: &MatchSettings) {
+    let mut first = HumanPlayer::new(settings.allow_undo).with_renderer(settings.renderer);
+    let mut second = HumanPlayer::new(settings.allow_undo).with_renderer(settings.renderer);
+    play_match(
+        state,
+        [&mut first, &mut second],
+        ["Player 1", "Player 2"],
+        Piece::X,
+        settings,
+    );
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum Turn {
     Player,
     Computer,
 }
 
-pub fn play_pvc() {
-    let mut board = vec![vec![None; 3]; 3];
+pub fn play_pvc(
+    state: GameState,
+    computer: ComputerPlayer,
+    human_piece: Piece,
+    settings: &MatchSettings,
+) {
+    let mut human = HumanPlayer::new(settings.allow_undo).with_renderer(settings.renderer);
+    let computer = computer.with_renderer(settings.renderer);
+    // the full screen shows the computer's moves itself
+    let mut computer = if settings.tui {
+        computer
+    } else {
+        computer.verbose()
+    };
+    play_match(
+        state,
+        [&mut human, &mut computer],
+        ["Player", "Computer"],
+        human_piece,
+        settings,
+    );
+}
 
-    let mut current_piece = Piece::X;
+/// Plays matches from `start` until nobody wants a rematch, swapping sides
+/// after every game. `first_side` is the piece `players[0]` starts with.
+fn play_match(
+    start: GameState,
+    players: [&mut dyn Player; 2],
+    names: [&str; 2],
+    first_side: Piece,
+    settings: &MatchSettings,
+) {
+    let [a, b] = players;
+    let format = settings.format;
+    // a single game needs no scoreboard
+    let keep_score = format != Format::BestOf(1);
+    // piped input holds moves, not answers
+    let interactive = std::io::stdin().is_terminal();
 
-    let mut turn: Turn;
-    let mut rng = rand::thread_rng();
-    match [Turn::Player, Turn::Computer].choose(&mut rng) {
-        Some(choice) => turn = *choice,
-        None => panic!("Failed to choose who goes first"),
-    }
+    let mut first_side = first_side;
+    loop {
+        let mut series = Series::new(format, names, first_side);
+        while !series.is_over() {
+            let side = series.next_side();
+            let (x, o): (&mut dyn Player, &mut dyn Player) = match side {
+                Piece::X => (&mut *a, &mut *b),
+                Piece::O => (&mut *b, &mut *a),
+            };
+            if keep_score {
+                println!("Game {}", series.games_played() + 1);
+            }
+            let first = if side == start.to_move() {
+                names[0]
+            } else {
+                names[1]
+            };
+            println!("{} plays {}, {} goes first!", names[0], side, first);
 
-    match turn {
-        Turn::Player => println!("Player goes first!"),
-        Turn::Computer => {
-            println!("Computer goes first!");
+            let outcome = if settings.tui {
+                match tui::run_game(start.clone(), x, o, settings.allow_undo, &settings.renderer) {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        eprintln!("The terminal failed: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                run_game(start.clone(), x, o, Some(&settings.renderer))
+            };
+            save_game(
+                settings.save_dir.as_deref(),
+                &outcome,
+                &x.name(Piece::X),
+                &o.name(Piece::O),
+            );
+            if let Err(forfeit) = &outcome {
+                let player: &dyn Player = match forfeit.piece {
+                    Piece::X => &*x,
+                    Piece::O => &*o,
+                };
+                // a person only resigns when their input runs out, and then
+                // nobody is left to play on
+                if player.is_human() {
+                    return;
+                }
+            }
+            let (game, result) = match outcome {
+                Ok(game) => {
+                    let result = game.result();
+                    (game, result)
+                }
+                Err(forfeit) => {
+                    let result = forfeit.result();
+                    (*forfeit.game, result)
+                }
+            };
+            if !result.is_over() {
+                // left the full screen mid-game
+                return;
+            }
+            if settings.tui {
+                display_board(game.board(), &settings.renderer);
+                match result.winner() {
+                    Some(Piece::X) => println!("{} wins!", x.name(Piece::X)),
+                    Some(Piece::O) => println!("{} wins!", o.name(Piece::O)),
+                    None => println!("Game over! It's a draw!"),
+                }
+            }
+            series.record(side, result);
+
+            if keep_score {
+                print!("{}", series);
+                if !series.is_over() && interactive && !confirm("Next game?", true) {
+                    break;
+                }
+            }
+        }
+        if keep_score {
+            println!("{}", series.summary());
         }
+
+        if !interactive || !confirm("Rematch?", false) {
+            return;
+        }
+        first_side = series.next_side();
     }
+}
 
+/// Asks a yes or no `question`. An empty answer means `default`, the end
+/// of input means no.
+fn confirm(question: &str, default: bool) -> bool {
+    let choices = if default { "Y/n" } else { "y/N" };
     loop {
-        let pos = if turn == Turn::Player {
-            display_board(&board);
-            println!("Player {}, enter your move [1..9]:", current_piece);
+        println!("{} [{}]", question, choices);
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+            return false;
+        }
+        match input.trim().to_lowercase().as_str() {
+            "" => return default,
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => println!("Please answer y or n."),
+        }
+    }
+}
+
+/// Saves the game, or the forfeited game with its result and the reason,
+/// in `dir` if there is one.
+fn save_game(dir: Option<&Path>, outcome: &Result<GameState, Forfeit>, x: &str, o: &str) {
+    let Some(dir) = dir else {
+        return;
+    };
+    let record = match outcome {
+        Ok(game) => GameRecord::new(game, x, o),
+        Err(forfeit) => GameRecord::new(&forfeit.game, x, o)
+            .with_result(forfeit.result())
+            .with_header("Termination", &forfeit.to_string()),
+    };
+    match record.save_in(dir) {
+        Ok(path) => println!("Game saved to {}", path.display()),
+        Err(e) => eprintln!("Could not save the game: {}", e),
+    }
+}
+
+pub fn do_replay(path: &Path, renderer: &Renderer) {
+    let record = match GameRecord::load(path) {
+        Ok(record) => record,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            std::process::exit(2);
+        }
+    };
+
+    for name in ["X", "O", "Date"] {
+        if let Some(value) = record.header(name) {
+            println!("{}: {}", name, value);
+        }
+    }
+    let mut game = record.start().clone();
+    display_board(game.board(), renderer);
 
+    // step on Enter when someone is watching, print straight through otherwise
+    let interactive = std::io::stdin().is_terminal();
+    for (ply, &pos) in record.moves().iter().enumerate() {
+        if interactive {
+            println!("Press Enter for the next move");
             let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
-            let input = input.trim();
+            if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+                return;
+            }
+        }
+        println!(
+            "{}. {} plays {}",
+            ply + 1,
+            game.to_move(),
+            renderer.format_move(game.board(), &pos)
+        );
+        game.play(pos).unwrap();
+        display_board(game.board(), renderer);
+    }
+
+    if let Some(termination) = record.header("Termination") {
+        println!("{}", termination);
+    }
+    match record.result().winner() {
+        Some(piece) => println!("{} wins!", piece),
+        None if record.result().is_over() => println!("Game over! It's a draw!"),
+        None => println!("The game was not finished."),
+    }
+}
 
-            match move_code_to_position(input) {
-                Some(p) => p,
-                None => {
-                    println!("Invalid input, try again.");
+pub fn do_serve(start: GameState, port: u16) {
+    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Could not listen on port {}: {}", port, e);
+            std::process::exit(2);
+        }
+    };
+    println!("Waiting for players on port {}", port);
+    if let Err(e) = net::serve(listener, start, true) {
+        eprintln!("Server stopped: {}", e);
+        std::process::exit(1);
+    }
+}
+
+pub fn do_connect(addr: &str, renderer: &Renderer) {
+    let stream = match TcpStream::connect(addr) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Could not connect to {}: {}", addr, e);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = net::play_remote(
+        stream,
+        &mut HumanPlayer::new(false).with_renderer(*renderer),
+        Some(renderer),
+    ) {
+        eprintln!("Lost the connection: {}", e);
+        std::process::exit(1);
+    }
+}
+
+pub fn do_analysis(start: GameState, position: &str, renderer: &Renderer) {
+    let mut fields = position.split_whitespace();
+    let is_notation = fields
+        .next()
+        .is_some_and(|rows| rows.chars().all(|c| "XO./".contains(c)))
+        && fields.next().is_some();
+    let state = match position.parse::<GameState>() {
+        Ok(state) => state,
+        Err(e) if is_notation => {
+            eprintln!("Invalid position: {}", e);
+            std::process::exit(2);
+        }
+        // not a position, so the moves played from the start
+        Err(_) => {
+            let mut state = start;
+            // commas separate moves, except where they are part of one
+            let row_col = renderer.scheme_for(state.board()) == Scheme::RowCol;
+            for input in position.split(|c: char| (c == ',' && !row_col) || c.is_whitespace()) {
+                if input.is_empty() {
                     continue;
                 }
+                let pos = renderer.parse_move(state.board(), input);
+                if let Err(e) = pos.and_then(|pos| state.play(pos)) {
+                    eprintln!("Invalid move {}: {}", input, e);
+                    std::process::exit(2);
+                }
             }
-        } else {
-            // let computers_move = get_random_valid_move(&mut rng, &board);
-            let computers_move = pick_best_move_par(&mut rng, &board, current_piece);
-            println!("Computer chose position {}", computers_move);
-            computers_move
-        };
+            state
+        }
+    };
+
+    println!("Position: {}", state.notation());
+    display_board(state.board(), renderer);
+    if state.result().is_over() {
+        println!("The game is over.");
+        return;
+    }
+    println!("Player {} to move:", state.to_move());
+    display_analysis(
+        state.board(),
+        &ai::analyze(state.board(), state.to_move()),
+        renderer,
+    );
+}
 
-        if !is_valid_move(&board, &pos) {
-            println!("Invalid move, try again.");
-            continue;
+pub fn do_solve(start: GameState, output: Option<&Path>, renderer: &Renderer) {
+    let now = std::time::Instant::now();
+    let table = match ai::solve::solve(start.board()) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Cannot solve this board: {}", e);
+            std::process::exit(2);
         }
+    };
+    println!(
+        "Solved {} positions in {:.2}ms",
+        table.len(),
+        now.elapsed().as_secs_f64() * 1000.0
+    );
 
-        apply_move(&mut board, &pos, current_piece);
-        if let Some(winner) = is_game_won(&board) {
-            display_board(&board);
-            match turn {
-                Turn::Player => println!("Player {} wins!", winner),
-                Turn::Computer => println!("Computer wins!"),
+    let solution = table.get(start.board()).unwrap();
+    let to_move = start.to_move();
+    match solution.value {
+        Value::Win => println!("{} to move wins in {}", to_move, solution.distance),
+        Value::Loss => println!("{} to move loses in {}", to_move, solution.distance),
+        Value::Draw => println!("{} to move draws", to_move),
+    }
+    let best: Vec<String> = table
+        .optimal_moves(start.board())
+        .iter()
+        .map(|pos| renderer.format_move(start.board(), pos))
+        .collect();
+    if !best.is_empty() {
+        println!("Best moves: {}", best.join(" "));
+    }
+
+    if let Some(path) = output {
+        match table.save(path) {
+            Ok(()) => println!("Solution saved to {}", path.display()),
+            Err(e) => {
+                eprintln!("Could not save the solution: {}", e);
+                std::process::exit(1);
             }
-            break;
-        }
-        if no_more_moves(&board) {
-            display_board(&board);
-            println!("Game over! It's a draw!");
-            break;
         }
-        current_piece = if current_piece == Piece::X {
-            Piece::O
-        } else {
-            Piece::X
+    }
+}
+
+pub fn do_selfplay(matches: &ArgMatches) {
+    let start = start_position(matches);
+    let games = *matches.get_one::<u32>("games").unwrap();
+    let save_dir = matches.get_one::<PathBuf>("save-dir").map(PathBuf::as_path);
+
+    // external engines are started once and told when each game begins
+    let mut x_engine = matches
+        .get_one::<String>("x")
+        .map(|program| external_engine(program, matches));
+    let mut o_engine = matches
+        .get_one::<String>("o")
+        .map(|program| external_engine(program, matches));
+
+    let (mut x_wins, mut o_wins, mut draws) = (0, 0, 0);
+    for _ in (0..games).progress_count(games as u64) {
+        let (mut x_computer, mut o_computer) = (computer_from(matches), computer_from(matches));
+        let x: &mut dyn Player = match &mut x_engine {
+            Some(engine) => engine,
+            None => &mut x_computer,
+        };
+        let o: &mut dyn Player = match &mut o_engine {
+            Some(engine) => engine,
+            None => &mut o_computer,
         };
-        turn = if turn == Turn::Player {
-            Turn::Computer
-        } else {
-            Turn::Player
+        let outcome = run_game(start.clone(), x, o, None);
+        let result = match &outcome {
+            Ok(game) => game.result(),
+            Err(forfeit) => {
+                eprintln!("{}", forfeit);
+                forfeit.result()
+            }
         };
+        match result.winner() {
+            Some(Piece::X) => x_wins += 1,
+            Some(Piece::O) => o_wins += 1,
+            None => draws += 1,
+        }
+        save_game(save_dir, &outcome, &x.name(Piece::X), &o.name(Piece::O));
+    }
+    println!("X wins: {}, O wins: {}, draws: {}", x_wins, o_wins, draws);
+}
+
+/// Starts the engine `program`, split into words, with `--movetime` from
+/// `engine_args`.
+fn external_engine(program: &str, matches: &ArgMatches) -> ExternalEngine {
+    let mut words = program.split_whitespace();
+    let mut command = std::process::Command::new(words.next().unwrap_or_default());
+    command.args(words);
+    let engine = match ExternalEngine::spawn(command) {
+        Ok(engine) => engine,
+        Err(e) => {
+            eprintln!("Could not start {}: {}", program, e);
+            std::process::exit(2);
+        }
+    };
+    match matches.get_one::<u64>("movetime") {
+        Some(&ms) => engine.with_movetime(Duration::from_millis(ms)),
+        None => engine,
+    }
+}
+
+pub fn do_engine(seed: Option<u64>) {
+    let engine = Engine::new();
+    let mut engine = match seed {
+        Some(seed) => engine.with_seed(seed),
+        None => engine,
+    };
+    if let Err(e) = engine.run(std::io::stdin().lock(), std::io::stdout().lock()) {
+        eprintln!("Engine stopped: {}", e);
+        std::process::exit(1);
     }
 }
 
-pub fn do_performance_check() {
-    const NUM_GAMES: u32 = 1000;
-    let total_time_taken_to_move: u128 = (0..NUM_GAMES)
-        .progress_count(NUM_GAMES as u64)
+pub fn do_performance_check(num_games: u32) {
+    let table = ai::tt::shared();
+
+    table.reset_stats();
+    let total_time_taken_to_move: u128 = (0..num_games)
+        .progress_count(num_games as u64)
         .map(|_| {
             let mut rng = rand::thread_rng();
-            let mut board = vec![vec![None; 3]; 3];
+            let board = Board::new();
 
             // random board with random pieces
             // (0..3).for_each(|x| {
@@ -212,6 +943,8 @@ pub fn do_performance_check() {
 
             let piece = Piece::X;
 
+            // start every search cold, so the timings are not just table lookups
+            table.clear();
             let now = std::time::Instant::now();
             pick_best_move(&mut rng, &board, piece);
             now.elapsed().as_nanos()
@@ -219,7 +952,7 @@ pub fn do_performance_check() {
         .sum();
 
     // compute stats
-    let average_time_taken_to_move = total_time_taken_to_move as f64 / NUM_GAMES as f64;
+    let average_time_taken_to_move = total_time_taken_to_move as f64 / num_games as f64;
     println!(
         "Average time taken to make a move: {:.2}ns",
         average_time_taken_to_move
@@ -232,11 +965,14 @@ pub fn do_performance_check() {
         average_time_taken_to_move
     );
 
-    let total_time_taken_to_move: u128 = (0..NUM_GAMES)
-        .progress_count(NUM_GAMES as u64)
+    report_table_stats(table.stats());
+
+    table.reset_stats();
+    let total_time_taken_to_move: u128 = (0..num_games)
+        .progress_count(num_games as u64)
         .map(|_| {
             let mut rng = rand::thread_rng();
-            let mut board = vec![vec![None; 3]; 3];
+            let board = Board::new();
 
             // random board with random pieces
             // (0..3).for_each(|x| {
@@ -258,6 +994,7 @@ pub fn do_performance_check() {
 
             let piece = Piece::X;
 
+            table.clear();
             let now = std::time::Instant::now();
             pick_best_move_par(&mut rng, &board, piece);
             now.elapsed().as_nanos()
@@ -265,7 +1002,7 @@ pub fn do_performance_check() {
         .sum();
 
     // compute stats
-    let average_time_taken_to_move = total_time_taken_to_move as f64 / NUM_GAMES as f64;
+    let average_time_taken_to_move = total_time_taken_to_move as f64 / num_games as f64;
     println!(
         "Average time taken to make a move with threading: {:.2}ns",
         average_time_taken_to_move
@@ -277,4 +1014,61 @@ pub fn do_performance_check() {
         "Average time taken to make a move with threading: {:.2}ms",
         average_time_taken_to_move
     );
+    report_table_stats(table.stats());
+
+    let now = std::time::Instant::now();
+    let solved = ai::solve::classic();
+    println!(
+        "Time taken to solve all {} positions: {:.2}ms",
+        solved.len(),
+        now.elapsed().as_secs_f64() * 1000.0
+    );
+    let total_time_taken_to_move: u128 = (0..num_games)
+        .map(|_| {
+            let board = Board::new();
+            let now = std::time::Instant::now();
+            solved.optimal_moves(&board);
+            now.elapsed().as_nanos()
+        })
+        .sum();
+    println!(
+        "Average time taken to look up a move: {:.2}ns",
+        total_time_taken_to_move as f64 / num_games as f64
+    );
+
+    // full games take longer, so play fewer of them
+    let num_self_play_games = num_games.div_ceil(10);
+    let total_time_taken_to_play: u128 = (0..num_self_play_games)
+        .progress_count(num_self_play_games as u64)
+        .map(|_| {
+            let mut rng = rand::thread_rng();
+            table.clear();
+            let now = std::time::Instant::now();
+            // searched, where `ComputerPlayer::minimax` would look the moves
+            // up in the solved table
+            let mut state = GameState::new(Board::new());
+            while !state.result().is_over() {
+                let pos = pick_best_move_par(&mut rng, state.board(), state.to_move());
+                state.play(pos).unwrap();
+            }
+            now.elapsed().as_nanos()
+        })
+        .sum();
+
+    let average_time_taken_to_play =
+        total_time_taken_to_play as f64 / num_self_play_games as f64 / 1_000_000.0;
+    println!(
+        "Average time taken to play a full game: {:.2}ms",
+        average_time_taken_to_play
+    );
+}
+
+fn report_table_stats(stats: ai::tt::TableStats) {
+    println!(
+        "Transposition table: {} probes, {} hits ({:.1}%), {} stores",
+        stats.probes,
+        stats.hits,
+        stats.hit_rate() * 100.0,
+        stats.stores
+    );
 }
@@ -0,0 +1,321 @@
+//! Games over TCP. A server owns the game and two clients take turns, one
+//! line of text per message. Moves are written in each board's standard
+//! notation, so a client can show them in whatever scheme it likes.
+//!
+//! The server sends:
+//!
+//! ```text
+//! HELLO x              you play X
+//! BOARD XO./.X./..O x  the position, in GameState::notation form
+//! YOURMOVE             it is your turn
+//! MOVED x 5            a move was played
+//! ERROR <reason>       your last message was refused
+//! RESULT x|o|draw      the game is over
+//! BYE <reason>         the game was abandoned
+//! ```
+//!
+//! and a client answers with `MOVE 5`, or leaves with `QUIT`.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc;
+
+use crate::game::{format_move, is_valid_move, parse_move, GameState, MoveError, Piece};
+use crate::player::{display_board, Action, Player};
+use crate::render::Renderer;
+
+fn side_code(piece: Piece) -> &'static str {
+    match piece {
+        Piece::X => "x",
+        Piece::O => "o",
+    }
+}
+
+fn parse_side(code: &str) -> Option<Piece> {
+    match code {
+        "x" => Some(Piece::X),
+        "o" => Some(Piece::O),
+        _ => None,
+    }
+}
+
+fn send(stream: &mut TcpStream, line: &str) -> io::Result<()> {
+    writeln!(stream, "{}", line)
+}
+
+/// Plays one game from `start` between the clients on `x` and `o`, and
+/// returns it. If a client leaves or its connection fails, the other is
+/// told and the game is returned unfinished.
+pub fn host_game(start: GameState, x: TcpStream, o: TcpStream) -> io::Result<GameState> {
+    // one reader per client, so a client leaving is noticed even when it is
+    // not its turn
+    let (events, inbox) = mpsc::channel();
+    let mut seats = Vec::new();
+    for (piece, stream) in [(Piece::X, x), (Piece::O, o)] {
+        stream.set_nodelay(true)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let events = events.clone();
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                if events.send((piece, Some(line))).is_err() {
+                    return;
+                }
+            }
+            let _ = events.send((piece, None));
+        });
+        seats.push(stream);
+    }
+    drop(events);
+
+    let result = run_host(start, &mut seats, &inbox);
+    for stream in &seats {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+    result
+}
+
+fn run_host(
+    mut state: GameState,
+    seats: &mut [TcpStream],
+    inbox: &mpsc::Receiver<(Piece, Option<String>)>,
+) -> io::Result<GameState> {
+    let seat = |piece| match piece {
+        Piece::X => 0,
+        Piece::O => 1,
+    };
+    // tells everyone still connected, ignoring whoever has gone
+    let broadcast = |seats: &mut [TcpStream], line: &str| {
+        for s in seats.iter_mut() {
+            let _ = send(s, line);
+        }
+    };
+    let abandon = |seats: &mut [TcpStream], piece: Piece, reason: &str| {
+        let _ = send(
+            &mut seats[seat(piece.opponent())],
+            &format!("BYE {}", reason),
+        );
+    };
+
+    // a client that has already gone is noticed by its reader, once both
+    // have seen the board
+    for piece in [Piece::X, Piece::O] {
+        let _ = send(
+            &mut seats[seat(piece)],
+            &format!("HELLO {}", side_code(piece)),
+        );
+    }
+
+    loop {
+        broadcast(seats, &format!("BOARD {}", state.notation()));
+        if state.result().is_over() {
+            let result = match state.result().winner() {
+                Some(piece) => side_code(piece),
+                None => "draw",
+            };
+            broadcast(seats, &format!("RESULT {}", result));
+            return Ok(state);
+        }
+
+        let mover = state.to_move();
+        if send(&mut seats[seat(mover)], "YOURMOVE").is_err() {
+            abandon(seats, mover, "opponent disconnected");
+            return Ok(state);
+        }
+
+        // wait for a legal move from the side to move
+        loop {
+            let Ok((from, line)) = inbox.recv() else {
+                return Ok(state);
+            };
+            let Some(line) = line else {
+                abandon(seats, from, "opponent disconnected");
+                return Ok(state);
+            };
+
+            let mut words = line.split_whitespace();
+            let reply = match (words.next(), words.next()) {
+                (Some("QUIT"), _) => {
+                    abandon(seats, from, "opponent left");
+                    return Ok(state);
+                }
+                (Some("MOVE"), _) if from != mover => "ERROR not your turn".to_string(),
+                (Some("MOVE"), Some(input)) => match parse_move(state.board(), input) {
+                    Ok(pos) if is_valid_move(state.board(), &pos) => {
+                        let code = format_move(state.board(), &pos);
+                        state.play(pos).unwrap();
+                        broadcast(seats, &format!("MOVED {} {}", side_code(mover), code));
+                        break;
+                    }
+                    Ok(_) => format!("ERROR {}", MoveError::Occupied),
+                    Err(e) => format!("ERROR {}", e),
+                },
+                _ => format!("ERROR unknown message: {}", line),
+            };
+            let _ = send(&mut seats[seat(from)], &reply);
+            if from == mover {
+                let _ = send(&mut seats[seat(from)], "YOURMOVE");
+            }
+        }
+    }
+}
+
+/// Hosts games from `start` on `listener` forever, pairing clients in the
+/// order they connect. The first of each pair plays X. Games run side by
+/// side; with `verbose` their results are printed.
+pub fn serve(listener: TcpListener, start: GameState, verbose: bool) -> io::Result<()> {
+    let mut games = 0;
+    loop {
+        let (x, x_addr) = listener.accept()?;
+        let (o, o_addr) = listener.accept()?;
+        games += 1;
+        if verbose {
+            println!("Game {}: {} plays X, {} plays O", games, x_addr, o_addr);
+        }
+
+        let start = start.clone();
+        std::thread::spawn(move || {
+            let outcome = host_game(start, x, o);
+            if !verbose {
+                return;
+            }
+            match outcome {
+                Ok(game) => match (game.result().winner(), game.result().is_over()) {
+                    (Some(piece), _) => println!("Game {}: {} wins", games, piece),
+                    (None, true) => println!("Game {}: draw", games),
+                    (None, false) => println!("Game {}: abandoned", games),
+                },
+                Err(e) => println!("Game {}: failed: {}", games, e),
+            }
+        });
+    }
+}
+
+/// Plays a game on a server as `player`, and returns the game as last
+/// seen, unfinished if the other side left. With a `display` renderer the
+/// board and what happens are printed.
+pub fn play_remote(
+    stream: TcpStream,
+    player: &mut dyn Player,
+    display: Option<&Renderer>,
+) -> io::Result<GameState> {
+    let bad_data = |line: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected message from the server: {}", line),
+        )
+    };
+
+    let mut writer = stream.try_clone()?;
+    let mut piece = None;
+    let mut state: Option<GameState> = None;
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            // the server went away, which ends the game as much as a BYE
+            Err(e) if is_hang_up(&e) => break,
+            Err(e) => return Err(e),
+        };
+        let (command, rest) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+        match command {
+            "HELLO" => {
+                let side = parse_side(rest).ok_or_else(|| bad_data(&line))?;
+                piece = Some(side);
+                if display.is_some() {
+                    println!("You play {}. Waiting for the game to start...", side);
+                }
+            }
+            "BOARD" => {
+                let board: GameState = rest.parse().map_err(|_| bad_data(&line))?;
+                // a person is shown the board with the prompt anyway
+                let prompted = player.is_human()
+                    && Some(board.to_move()) == piece
+                    && !board.result().is_over();
+                if let (Some(renderer), false) = (display, prompted) {
+                    display_board(board.board(), renderer);
+                }
+                state = Some(board);
+            }
+            "MOVED" => {
+                let (side, code) = rest.split_once(' ').ok_or_else(|| bad_data(&line))?;
+                let (Some(side), Some(state)) = (parse_side(side), &state) else {
+                    return Err(bad_data(&line));
+                };
+                match display {
+                    Some(renderer) if Some(side) != piece => {
+                        let pos = parse_move(state.board(), code).map_err(|_| bad_data(&line))?;
+                        println!(
+                            "Opponent played {}",
+                            renderer.format_move(state.board(), &pos)
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            "YOURMOVE" => {
+                let state = state.as_ref().ok_or_else(|| bad_data(&line))?;
+                let message = loop {
+                    match player.choose_action(state) {
+                        Action::Play(pos) => {
+                            break format!("MOVE {}", format_move(state.board(), &pos))
+                        }
+                        Action::Resign(reason) => {
+                            if display.is_some() {
+                                println!("You resigned: {}", reason);
+                            }
+                            break "QUIT".to_string();
+                        }
+                        Action::Undo | Action::Redo => {
+                            if display.is_some() {
+                                println!("Moves cannot be taken back in a game over the network.");
+                            }
+                        }
+                    }
+                };
+                match writeln!(writer, "{}", message) {
+                    // the server says why it hung up in the lines still to read
+                    Err(e) if !is_hang_up(&e) => return Err(e),
+                    _ => {}
+                }
+            }
+            "ERROR" => {
+                if display.is_some() {
+                    println!("Move refused: {}", rest);
+                }
+            }
+            "RESULT" => {
+                if display.is_some() {
+                    match parse_side(rest) {
+                        Some(side) if Some(side) == piece => println!("You win!"),
+                        Some(_) => println!("You lose!"),
+                        None => println!("Game over! It's a draw!"),
+                    }
+                }
+                break;
+            }
+            "BYE" => {
+                if display.is_some() {
+                    println!("The game was abandoned: {}", rest);
+                }
+                break;
+            }
+            _ => return Err(bad_data(&line)),
+        }
+    }
+
+    state.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the server closed the connection before the game started",
+        )
+    })
+}
+
+fn is_hang_up(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
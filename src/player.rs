@@ -0,0 +1,413 @@
+//! The participants in a game and the loop that runs one.
+
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::ai::{self, Difficulty, MoveAnalysis, Outcome, Selection, Strategy};
+use crate::game::{format_move, Board, GameResult, GameState, Piece, Position};
+use crate::render::Renderer;
+
+/// Anything that can take a turn: a person at the keyboard, an AI or a
+/// script.
+pub trait Player {
+    /// How the player is referred to in messages, e.g. "Player X".
+    fn name(&self, piece: Piece) -> String;
+
+    /// Picks a move for `state.to_move()`. The runner asks again if the move
+    /// is not legal.
+    fn choose_move(&mut self, state: &GameState) -> Position;
+
+    /// Picks what to do with the turn. Players that only ever move can
+    /// leave this to `choose_move`.
+    fn choose_action(&mut self, state: &GameState) -> Action {
+        Action::Play(self.choose_move(state))
+    }
+
+    /// Whether a person makes the choices. Undo and redo step past the
+    /// turns of players that are not, so a person always gets the turn back.
+    fn is_human(&self) -> bool {
+        false
+    }
+}
+
+/// What a player does with its turn.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Action {
+    /// Plays a move.
+    Play(Position),
+    /// Takes back moves up to the player's previous turn.
+    Undo,
+    /// Plays moves taken back by `Undo` again.
+    Redo,
+    /// Gives up the game, for the reason given, e.g. an engine that
+    /// stopped answering.
+    Resign(String),
+}
+
+/// A game that ended because one side resigned or, not being a person who
+/// could try again, played an illegal move.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Forfeit {
+    /// The side that forfeited.
+    pub piece: Piece,
+    /// Why.
+    pub reason: String,
+    /// The game as it stood.
+    pub game: Box<GameState>,
+}
+
+impl Forfeit {
+    /// The result the forfeit gives: a win for the other side.
+    pub fn result(&self) -> GameResult {
+        match self.piece {
+            Piece::X => GameResult::OWins,
+            Piece::O => GameResult::XWins,
+        }
+    }
+}
+
+impl std::fmt::Display for Forfeit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} forfeits: {}", self.piece, self.reason)
+    }
+}
+
+impl std::error::Error for Forfeit {}
+
+/// Reads moves from stdin, and resigns if stdin closes.
+pub struct HumanPlayer {
+    /// Accepts the undo and redo commands.
+    pub allow_undo: bool,
+    /// Draws the board and reads moves in its scheme.
+    pub renderer: Renderer,
+}
+
+impl HumanPlayer {
+    /// A player at the keyboard, who may take moves back if `allow_undo`,
+    /// shown plain boards.
+    pub fn new(allow_undo: bool) -> Self {
+        HumanPlayer {
+            allow_undo,
+            renderer: Renderer::PLAIN,
+        }
+    }
+
+    /// Shows the player boards drawn by `renderer`, and reads their moves in
+    /// its scheme.
+    pub fn with_renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+}
+
+impl Player for HumanPlayer {
+    fn name(&self, piece: Piece) -> String {
+        format!("Player {}", piece)
+    }
+
+    /// The runners ask with `choose_action`, which resigns when stdin
+    /// closes; this has no way to say so, and panics instead.
+    fn choose_move(&mut self, state: &GameState) -> Position {
+        loop {
+            match self.choose_action(state) {
+                Action::Play(pos) => return pos,
+                Action::Resign(reason) => panic!("Player resigned: {}", reason),
+                Action::Undo | Action::Redo => {}
+            }
+        }
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Action {
+        loop {
+            display_board(state.board(), &self.renderer);
+            let commands = if self.allow_undo {
+                ", hint, undo or redo"
+            } else {
+                " or hint"
+            };
+            println!(
+                "Player {}, enter your move {}{}:",
+                state.to_move(),
+                self.renderer
+                    .scheme_for(state.board())
+                    .describe(state.board()),
+                commands
+            );
+
+            let mut input = String::new();
+            match std::io::stdin().read_line(&mut input) {
+                // nobody is left to play
+                Ok(0) => return Action::Resign("stdin closed".to_string()),
+                Ok(_) => {}
+                Err(e) => return Action::Resign(format!("could not read stdin: {}", e)),
+            }
+
+            match input.trim() {
+                "hint" => {
+                    display_analysis(
+                        state.board(),
+                        &ai::analyze(state.board(), state.to_move()),
+                        &self.renderer,
+                    );
+                    continue;
+                }
+                "u" | "undo" | "r" | "redo" if !self.allow_undo => {
+                    println!("Undo is turned off for this game.");
+                    continue;
+                }
+                "u" | "undo" => return Action::Undo,
+                "r" | "redo" => return Action::Redo,
+                _ => {}
+            }
+
+            match self.renderer.parse_move(state.board(), input.trim()) {
+                Ok(p) => return Action::Play(p),
+                Err(e) => println!("Invalid input ({}), try again.", e),
+            }
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+}
+
+/// Prints `board` to stdout as `renderer` draws it.
+pub fn display_board(board: &Board, renderer: &Renderer) {
+    print!("{}", renderer.render(board));
+}
+
+/// Prints what the engine thinks of each analysed move, best first, with
+/// the moves written in `renderer`'s scheme, and says so when that is not
+/// every legal move.
+pub fn display_analysis(board: &Board, analysis: &[MoveAnalysis], renderer: &Renderer) {
+    println!("{:>4}  {:<12} {:>10}  line", "move", "outcome", "score");
+    for a in analysis {
+        let outcome = match (a.outcome, a.distance) {
+            (Outcome::Win, Some(d)) => format!("win in {}", d),
+            (Outcome::Loss, Some(d)) => format!("loss in {}", d),
+            (Outcome::Draw, _) => "draw".to_string(),
+            _ => "unclear".to_string(),
+        };
+        let line: Vec<String> =
+            a.pv.iter()
+                .map(|pos| renderer.format_move(board, pos))
+                .collect();
+        println!(
+            "{:>4}  {:<12} {:>10}  {}",
+            renderer.format_move(board, &a.pos),
+            outcome,
+            a.score,
+            line.join(" ")
+        );
+    }
+    let legal = board.empty().count_ones() as usize;
+    if analysis.len() < legal {
+        println!(
+            "Only {} of the {} legal moves are shown; on a board this size the rest are not searched.",
+            analysis.len(),
+            legal
+        );
+    }
+}
+
+/// Plays whatever `strategy` picks.
+pub struct ComputerPlayer {
+    /// How moves are found.
+    pub strategy: Strategy,
+    /// How to choose among equally good moves.
+    pub selection: Selection,
+    /// Prints each chosen move.
+    pub verbose: bool,
+    /// Writes the printed moves in its scheme.
+    pub renderer: Renderer,
+    rng: StdRng,
+}
+
+impl ComputerPlayer {
+    /// A quiet computer player.
+    pub fn new(strategy: Strategy, selection: Selection) -> Self {
+        ComputerPlayer {
+            strategy,
+            selection,
+            verbose: false,
+            renderer: Renderer::PLAIN,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// A computer that plays at random.
+    pub fn random() -> Self {
+        Self::new(Strategy::Random, Selection::Best)
+    }
+
+    /// A computer that plays perfectly.
+    pub fn minimax() -> Self {
+        Self::new(Difficulty::Perfect.strategy(), Selection::Best)
+    }
+
+    /// Makes the computer print its moves.
+    pub fn verbose(mut self) -> Self {
+        self.verbose = true;
+        self
+    }
+
+    /// Makes the computer write the moves it prints in `renderer`'s scheme.
+    pub fn with_renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Makes the computer's choices repeatable: the same seed in the same
+    /// game gives the same moves.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Player for ComputerPlayer {
+    fn name(&self, piece: Piece) -> String {
+        format!("Computer {}", piece)
+    }
+
+    fn choose_move(&mut self, state: &GameState) -> Position {
+        let pos = self.strategy.pick_move(
+            &mut self.rng,
+            state.board(),
+            state.to_move(),
+            self.selection,
+        );
+        if self.verbose {
+            println!(
+                "Computer chose position {}",
+                self.renderer.format_move(state.board(), &pos)
+            );
+        }
+        pos
+    }
+}
+
+/// Plays a fixed list of moves, in order.
+pub struct ScriptedPlayer {
+    moves: VecDeque<Position>,
+}
+
+impl ScriptedPlayer {
+    /// A player that plays `moves` in order.
+    pub fn new(moves: impl IntoIterator<Item = Position>) -> Self {
+        ScriptedPlayer {
+            moves: moves.into_iter().collect(),
+        }
+    }
+}
+
+impl Player for ScriptedPlayer {
+    fn name(&self, piece: Piece) -> String {
+        format!("Script {}", piece)
+    }
+
+    fn choose_move(&mut self, _state: &GameState) -> Position {
+        self.moves
+            .pop_front()
+            .expect("Scripted player ran out of moves")
+    }
+}
+
+/// Plays one game between `x` and `o` from `state` and returns the
+/// finished game, or the forfeit that ended it early. With a `display`
+/// renderer the final board and result are printed.
+pub fn run_game(
+    mut state: GameState,
+    x: &mut dyn Player,
+    o: &mut dyn Player,
+    display: Option<&Renderer>,
+) -> Result<GameState, Forfeit> {
+    let humans = (x.is_human(), o.is_human());
+    let is_human = |piece| match piece {
+        Piece::X => humans.0,
+        Piece::O => humans.1,
+    };
+
+    while !state.result().is_over() {
+        let piece = state.to_move();
+        let player: &mut dyn Player = match piece {
+            Piece::X => &mut *x,
+            Piece::O => &mut *o,
+        };
+
+        let pos = match player.choose_action(&state) {
+            Action::Play(pos) => pos,
+            Action::Resign(reason) => {
+                let forfeit = Forfeit {
+                    piece,
+                    reason,
+                    game: Box::new(state),
+                };
+                if let Some(renderer) = display {
+                    display_board(forfeit.game.board(), renderer);
+                    println!("{} forfeits: {}", player.name(piece), forfeit.reason);
+                }
+                return Err(forfeit);
+            }
+            Action::Undo => {
+                if !step_back_to_human(&mut state, GameState::undo, is_human) && display.is_some() {
+                    println!("There is nothing to undo.");
+                }
+                continue;
+            }
+            Action::Redo => {
+                if !step_back_to_human(&mut state, GameState::redo, is_human) && display.is_some() {
+                    println!("There is nothing to redo.");
+                }
+                continue;
+            }
+        };
+        if let Err(e) = state.play(pos) {
+            // only a person can do better by being asked again
+            if !is_human(piece) {
+                let forfeit = Forfeit {
+                    piece,
+                    reason: format!("illegal move {} ({})", format_move(state.board(), &pos), e),
+                    game: Box::new(state),
+                };
+                if let Some(renderer) = display {
+                    display_board(forfeit.game.board(), renderer);
+                    println!("{} forfeits: {}", player.name(piece), forfeit.reason);
+                }
+                return Err(forfeit);
+            }
+            if display.is_some() {
+                println!("Invalid move ({}), try again.", e);
+            }
+            continue;
+        }
+    }
+
+    if let Some(renderer) = display {
+        display_board(state.board(), renderer);
+        match state.result().winner() {
+            Some(Piece::X) => println!("{} wins!", x.name(Piece::X)),
+            Some(Piece::O) => println!("{} wins!", o.name(Piece::O)),
+            None => println!("Game over! It's a draw!"),
+        }
+    }
+    Ok(state)
+}
+
+/// Undoes or redoes one move with `step`, then keeps going while it is a
+/// computer's turn, so that in a game against the computer one undo takes
+/// back both its reply and the move before. Returns whether anything
+/// changed.
+pub(crate) fn step_back_to_human(
+    state: &mut GameState,
+    step: fn(&mut GameState) -> Option<Position>,
+    is_human: impl Fn(Piece) -> bool,
+) -> bool {
+    if step(state).is_none() {
+        return false;
+    }
+    while !is_human(state.to_move()) && !state.result().is_over() && step(state).is_some() {}
+    true
+}
@@ -0,0 +1,353 @@
+//! Game records in a PGN-style text format: a few `[Name "value"]`
+//! headers, then the numbered move list and the result.
+//!
+//! ```text
+//! [X "Player X"]
+//! [O "Computer O"]
+//! [Date "2026.10.17"]
+//! [Variant "3,3,3"]
+//! [Result "1/2-1/2"]
+//!
+//! 1. 5 7 2. 8 2 3. 1 9 4. 6 4 5. 3 1/2-1/2
+//! ```
+//!
+//! Moves use the board's own notation, which on the classic board is the
+//! numpad code. A game that did not start on an empty board also gets a
+//! `Position` header in `GameState::notation` form, and one where O moved
+//! first on an empty board gets a `First` header. A game that ended early,
+//! e.g. because one side forfeited, says why in a `Termination` header and
+//! records the result it was given rather than the one on the board.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::game::{
+    format_move, parse_move, Board, GameResult, GameState, MoveError, Piece, Position,
+    PositionError,
+};
+
+/// Why a record could not be read.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RecordError {
+    /// A header line is not of the form `[Name "value"]`.
+    BadHeader(String),
+    /// The `Variant` header is not `width,height,k` for a supported board.
+    BadVariant(String),
+    /// The `Position` header is not a valid position.
+    BadPosition(PositionError),
+    /// The `First` header is not `X` or `O`.
+    BadFirst(String),
+    /// A move could not be read or played. Plies count from 1.
+    BadMove {
+        /// The ply the move was played at.
+        ply: usize,
+        /// What was wrong with it.
+        error: MoveError,
+    },
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RecordError::BadHeader(line) => write!(f, "bad header line: {}", line),
+            RecordError::BadVariant(variant) => write!(f, "unknown variant: {}", variant),
+            RecordError::BadPosition(e) => write!(f, "bad starting position: {}", e),
+            RecordError::BadFirst(first) => write!(f, "unknown first player: {}", first),
+            RecordError::BadMove { ply, error } => write!(f, "bad move at ply {}: {}", ply, error),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// A game from its starting position to where it stopped, with headers.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GameRecord {
+    headers: Vec<(String, String)>,
+    start: GameState,
+    moves: Vec<Position>,
+    result: GameResult,
+}
+
+impl GameRecord {
+    /// A record of `game` between the players named `x` and `o`, dated
+    /// today.
+    pub fn new(game: &GameState, x: &str, o: &str) -> Self {
+        let start = game.start();
+
+        let board = game.board();
+        let mut headers = vec![
+            ("X".to_string(), x.to_string()),
+            ("O".to_string(), o.to_string()),
+            ("Date".to_string(), today()),
+            (
+                "Variant".to_string(),
+                format!("{},{},{}", board.width(), board.height(), board.k()),
+            ),
+        ];
+        if !start.board().occupied().is_empty() {
+            headers.push(("Position".to_string(), start.notation()));
+        } else if start.to_move() == Piece::O {
+            headers.push(("First".to_string(), Piece::O.to_string()));
+        }
+        headers.push(("Result".to_string(), result_code(game.result()).to_string()));
+
+        GameRecord {
+            headers,
+            start,
+            moves: game.moves().to_vec(),
+            result: game.result(),
+        }
+    }
+
+    /// Records `result` instead of the one on the board, for a game decided
+    /// some other way, e.g. by a forfeit.
+    pub fn with_result(mut self, result: GameResult) -> Self {
+        self.result = result;
+        self.set_header("Result", result_code(result));
+        self
+    }
+
+    /// Adds the header `name`, or replaces its value if already present.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.set_header(name, value);
+        self
+    }
+
+    fn set_header(&mut self, name: &str, value: &str) {
+        match self.headers.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The value of the header `name`, if present.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The position the game started from.
+    pub fn start(&self) -> &GameState {
+        &self.start
+    }
+
+    /// The moves played, in order.
+    pub fn moves(&self) -> &[Position] {
+        &self.moves
+    }
+
+    /// How the game ended, which for a forfeit is not what the board says.
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    /// The game with every move played.
+    pub fn game(&self) -> GameState {
+        let mut game = self.start.clone();
+        for &pos in &self.moves {
+            // moves are checked when a record is read or made from a game
+            game.play(pos).unwrap();
+        }
+        game
+    }
+
+    /// Writes the record to the file at `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    /// Reads a record from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the record to a new file in `dir`, named after the current
+    /// time, and returns its path.
+    pub fn save_in(&self, dir: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        // several games can finish within the same second
+        let mut n = 0;
+        loop {
+            let name = match n {
+                0 => format!("game-{}.pgn", stamp),
+                _ => format!("game-{}-{}.pgn", stamp, n),
+            };
+            let path = dir.join(name);
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(file) => {
+                    io::Write::write_all(&mut &file, self.to_string().as_bytes())?;
+                    return Ok(path);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (name, value) in &self.headers {
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut game = self.start.clone();
+        let mut tokens = Vec::new();
+        let mut number = 1;
+        for &pos in &self.moves {
+            match game.to_move() {
+                Piece::X => tokens.push(format!("{}.", number)),
+                // a game set up with O to move starts mid-turn
+                Piece::O if tokens.is_empty() => {
+                    tokens.push(format!("{}...", number));
+                    number += 1;
+                }
+                Piece::O => number += 1,
+            }
+            tokens.push(format_move(game.board(), &pos));
+            game.play(pos).unwrap();
+        }
+        tokens.push(result_code(self.result).to_string());
+        writeln!(f, "{}", tokens.join(" "))
+    }
+}
+
+impl std::str::FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut headers = Vec::new();
+        let mut movetext = String::new();
+        for line in s.lines().map(str::trim) {
+            if line.starts_with('[') {
+                headers.push(parse_header(line)?);
+            } else {
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _): &&(String, String)| n == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let first = match header("First") {
+            None | Some("X") => Piece::X,
+            Some("O") => Piece::O,
+            Some(first) => return Err(RecordError::BadFirst(first.to_string())),
+        };
+        let mut start = match header("Position") {
+            Some(position) => position.parse().map_err(RecordError::BadPosition)?,
+            None => GameState::with_first(Board::new(), first),
+        };
+        if let Some(variant) = header("Variant") {
+            let bad = || RecordError::BadVariant(variant.to_string());
+            let size: Vec<u8> = variant
+                .split(',')
+                .map(|n| n.trim().parse().map_err(|_| bad()))
+                .collect::<Result<_, _>>()?;
+            let [width, height, k] = size[..] else {
+                return Err(bad());
+            };
+            let board = start.board();
+            if header("Position").is_none() {
+                let board = Board::with_size(width, height, k).map_err(|_| bad())?;
+                start = GameState::with_first(board, first);
+            } else if (board.width(), board.height(), board.k()) != (width, height, k) {
+                return Err(bad());
+            }
+        }
+
+        let mut game = start.clone();
+        let mut moves = Vec::new();
+        for token in movetext.split_whitespace() {
+            // move numbers and the result are implied by the moves
+            if token.ends_with('.') || ["1-0", "0-1", "1/2-1/2", "*"].contains(&token) {
+                continue;
+            }
+            let ply = moves.len() + 1;
+            let pos = parse_move(game.board(), token)
+                .and_then(|pos| game.play(pos).map(|_| pos))
+                .map_err(|error| RecordError::BadMove { ply, error })?;
+            moves.push(pos);
+        }
+        // a game that ended early keeps the result it was given
+        let result = match header("Result") {
+            Some("1-0") => GameResult::XWins,
+            Some("0-1") => GameResult::OWins,
+            Some("1/2-1/2") => GameResult::Draw,
+            _ => game.result(),
+        };
+
+        Ok(GameRecord {
+            headers,
+            start,
+            moves,
+            result,
+        })
+    }
+}
+
+fn parse_header(line: &str) -> Result<(String, String), RecordError> {
+    let bad = || RecordError::BadHeader(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(bad)?;
+    let (name, value) = inner.split_once(' ').ok_or_else(bad)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(bad)?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+fn result_code(result: GameResult) -> &'static str {
+    match result {
+        GameResult::XWins => "1-0",
+        GameResult::OWins => "0-1",
+        GameResult::Draw => "1/2-1/2",
+        GameResult::Ongoing => "*",
+    }
+}
+
+/// Today's UTC date as `YYYY.MM.DD`.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+        / 86_400;
+
+    // civil-from-days, counting in 400-year eras from 0000-03-01
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,308 @@
+//! Drawing boards as text, in a choice of styles and with or without ANSI
+//! colors. Rendering only builds strings; `player::display_board` prints
+//! them with whichever renderer it is given.
+//!
+//! ```
+//! use ppttt::game::Board;
+//! use ppttt::render::{Renderer, Style};
+//!
+//! let renderer = Renderer {
+//!     style: Style::Ascii,
+//!     color: false,
+//!     scheme: None,
+//! };
+//! assert_eq!(
+//!     renderer.render(&Board::new()),
+//!     " 7 | 8 | 9\n---+---+---\n 4 | 5 | 6\n---+---+---\n 1 | 2 | 3\n"
+//! );
+//! ```
+
+use std::io::IsTerminal;
+
+use crate::game::{winning_line, Board, ParseError, Piece, Position, Scheme};
+
+/// How the board is laid out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Style {
+    /// One character per cell.
+    Plain,
+    /// A grid drawn with `|`, `-` and `+`.
+    Ascii,
+    /// A grid drawn with Unicode box-drawing characters.
+    Unicode,
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Style::Plain),
+            "ascii" => Ok(Style::Ascii),
+            "unicode" | "box" => Ok(Style::Unicode),
+            _ => Err(format!("unknown style: {}", s)),
+        }
+    }
+}
+
+/// When to color the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorChoice {
+    /// When stdout is a terminal and `NO_COLOR` is not set.
+    Auto,
+    /// Always, even into a pipe.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color what is printed to stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::env::var("TERM").as_deref() != Ok("dumb")
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice: {}", s)),
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const X_COLOR: &str = "\x1b[1;36m";
+const O_COLOR: &str = "\x1b[1;35m";
+const WINNING: &str = "\x1b[1;30;42m";
+
+/// The characters that draw a grid.
+struct Grid {
+    /// Left, middle and right of the line above the board, if any.
+    top: Option<[&'static str; 3]>,
+    /// Left, middle and right of the lines between rows.
+    between: [&'static str; 3],
+    /// Left, middle and right of the line below the board, if any.
+    bottom: Option<[&'static str; 3]>,
+    /// The outer edges of a row of cells.
+    edge: &'static str,
+    /// Between two cells in a row.
+    wall: &'static str,
+    /// Repeated under or over a cell.
+    rule: &'static str,
+}
+
+const ASCII: Grid = Grid {
+    top: None,
+    between: ["", "+", ""],
+    bottom: None,
+    edge: "",
+    wall: "|",
+    rule: "-",
+};
+
+const UNICODE: Grid = Grid {
+    top: Some(["┌", "┬", "┐"]),
+    between: ["├", "┼", "┤"],
+    bottom: Some(["└", "┴", "┘"]),
+    edge: "│",
+    wall: "│",
+    rule: "─",
+};
+
+/// Draws boards in one style and writes moves in one scheme.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Renderer {
+    /// How the board is laid out.
+    pub style: Style,
+    /// Whether to use ANSI colors: pieces in color, the cell hints dimmed
+    /// and the winning line highlighted.
+    pub color: bool,
+    /// How cells are named, or `None` for each board's standard scheme.
+    pub scheme: Option<Scheme>,
+}
+
+impl Renderer {
+    /// Plain and uncolored in the standard scheme, which is how `Board`
+    /// displays itself.
+    pub const PLAIN: Renderer = Renderer {
+        style: Style::Plain,
+        color: false,
+        scheme: None,
+    };
+
+    /// The scheme cells of `board` are written in.
+    pub fn scheme_for(&self, board: &Board) -> Scheme {
+        self.scheme.unwrap_or_else(|| Scheme::standard(board))
+    }
+
+    /// Reads a move typed in this renderer's scheme.
+    pub fn parse_move(&self, board: &Board, input: &str) -> Result<Position, ParseError> {
+        self.scheme_for(board).parse(board, input)
+    }
+
+    /// Writes a move in this renderer's scheme.
+    pub fn format_move(&self, board: &Board, pos: &Position) -> String {
+        self.scheme_for(board).format(board, pos)
+    }
+
+    /// `board` as lines of text, each ending in a newline.
+    pub fn render(&self, board: &Board) -> String {
+        let line = winning_line(board).unwrap_or_default();
+        match self.style {
+            Style::Plain => self.render_plain(board, &line),
+            Style::Ascii => self.render_grid(board, &line, &ASCII),
+            Style::Unicode => self.render_grid(board, &line, &UNICODE),
+        }
+    }
+
+    fn render_plain(&self, board: &Board, line: &[Position]) -> String {
+        let scheme = self.scheme_for(board);
+        let mut out = String::new();
+        let cells = board.width() as usize * board.height() as usize;
+        if matches!(scheme, Scheme::Numpad | Scheme::Phone) {
+            if cells > 9 {
+                // the numbers no longer fit in one character
+                return self.render_grid(board, line, &ASCII);
+            }
+            for y in 0..board.height() {
+                for x in 0..board.width() {
+                    let pos = Position { x, y };
+                    out.push_str(&self.cell(board, pos, line, &scheme.format(board, &pos)));
+                }
+                out.push('\n');
+            }
+            return out;
+        }
+
+        // other schemes get coordinates around the board instead
+        let height = board.height();
+        for y in 0..height {
+            let row = match scheme {
+                Scheme::RowCol => y + 1,
+                _ => height - y,
+            };
+            out.push_str(&format!("{:>2} ", row));
+            for x in 0..board.width() {
+                out.push(' ');
+                out.push_str(&self.cell(board, Position { x, y }, line, "."));
+            }
+            out.push('\n');
+        }
+        out.push_str("   ");
+        for x in 0..board.width() {
+            match scheme {
+                Scheme::RowCol => out.push_str(&format!("{:>2}", x + 1)),
+                _ => out.push_str(&format!("{:>2}", (b'a' + x) as char)),
+            }
+        }
+        out.push('\n');
+        out
+    }
+
+    /// Every cell's name in this renderer's scheme, row by row.
+    pub fn hints(&self, board: &Board) -> Vec<Vec<String>> {
+        let scheme = self.scheme_for(board);
+        (0..board.height())
+            .map(|y| {
+                (0..board.width())
+                    .map(|x| scheme.format(board, &Position { x, y }))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn render_grid(&self, board: &Board, line: &[Position], grid: &Grid) -> String {
+        let hints = self.hints(board);
+        let inner = cell_width(&hints);
+
+        let width = board.width() as usize;
+        let rule = |[left, middle, right]: [&str; 3]| {
+            let rules = vec![grid.rule.repeat(inner); width];
+            format!("{}{}{}\n", left, rules.join(middle), right)
+        };
+
+        let mut out = String::new();
+        if let Some(top) = grid.top {
+            out.push_str(&rule(top));
+        }
+        for (y, hints) in hints.iter().enumerate() {
+            if y > 0 {
+                out.push_str(&rule(grid.between));
+            }
+            let cells: Vec<String> = hints
+                .iter()
+                .enumerate()
+                .map(|(x, hint)| {
+                    let pos = Position {
+                        x: x as u8,
+                        y: y as u8,
+                    };
+                    let cell = self.cell(board, pos, line, hint);
+                    let (text, len) = match board.get(&pos) {
+                        // without color the winning line is bracketed instead
+                        Some(_) if line.contains(&pos) && !self.color => (format!("[{}]", cell), 3),
+                        Some(_) => (cell, 1),
+                        None => (cell, hint.len()),
+                    };
+                    let left = (inner - len) / 2;
+                    format!(
+                        "{}{}{}",
+                        " ".repeat(left),
+                        text,
+                        " ".repeat(inner - len - left)
+                    )
+                })
+                .collect();
+            let row = format!("{}{}{}", grid.edge, cells.join(grid.wall), grid.edge);
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+        if let Some(bottom) = grid.bottom {
+            out.push_str(&rule(bottom));
+        }
+        out
+    }
+
+    /// The text for the cell at `pos`: its piece, or `empty` when there is
+    /// none.
+    fn cell(&self, board: &Board, pos: Position, line: &[Position], empty: &str) -> String {
+        let (text, color) = match board.get(&pos) {
+            Some(piece) if line.contains(&pos) => (piece.to_string(), WINNING),
+            Some(Piece::X) => ("X".to_string(), X_COLOR),
+            Some(Piece::O) => ("O".to_string(), O_COLOR),
+            None => (empty.to_string(), DIM),
+        };
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text
+        }
+    }
+}
+
+/// The columns inside a grid cell: wide enough for the longest of `hints`,
+/// and for a piece with room around it.
+pub fn cell_width(hints: &[Vec<String>]) -> usize {
+    hints
+        .iter()
+        .flatten()
+        .map(String::len)
+        .max()
+        .unwrap_or(0)
+        .max(3)
+}
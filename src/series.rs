@@ -0,0 +1,178 @@
+//! Matches of several games between the same two players, who swap sides
+//! after every game, and the score they keep.
+
+use crate::game::{GameResult, Piece};
+
+/// When a match is over.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// After `n` games, or as soon as the trailing player cannot catch up.
+    BestOf(u32),
+    /// As soon as a player has won `n` games. Draws do not count.
+    FirstTo(u32),
+}
+
+/// Wins, losses and draws from one player's point of view.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Tally {
+    /// Games won.
+    pub wins: u32,
+    /// Games lost.
+    pub losses: u32,
+    /// Games drawn.
+    pub draws: u32,
+}
+
+impl Tally {
+    fn add(&mut self, result: Option<bool>) {
+        match result {
+            Some(true) => self.wins += 1,
+            Some(false) => self.losses += 1,
+            None => self.draws += 1,
+        }
+    }
+}
+
+/// A match between two players, numbered 0 and 1.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Series {
+    format: Format,
+    names: [String; 2],
+    first_side: Piece,
+    /// Player 0's piece and the result of every finished game.
+    games: Vec<(Piece, GameResult)>,
+}
+
+impl Series {
+    /// A match in `format` where player 0 plays `first_side` in the first
+    /// game.
+    pub fn new(format: Format, names: [&str; 2], first_side: Piece) -> Self {
+        Series {
+            format,
+            names: names.map(str::to_string),
+            first_side,
+            games: Vec::new(),
+        }
+    }
+
+    /// The player's name, as given to `new`.
+    pub fn name(&self, player: usize) -> &str {
+        &self.names[player]
+    }
+
+    /// Number of games finished so far.
+    pub fn games_played(&self) -> u32 {
+        self.games.len() as u32
+    }
+
+    /// The piece player 0 plays in the next game.
+    pub fn next_side(&self) -> Piece {
+        match self.games.len() % 2 {
+            0 => self.first_side,
+            _ => self.first_side.opponent(),
+        }
+    }
+
+    /// Counts a finished game in which player 0 played `side`.
+    pub fn record(&mut self, side: Piece, result: GameResult) {
+        self.games.push((side, result));
+    }
+
+    /// The player's results over the whole match.
+    pub fn tally(&self, player: usize) -> Tally {
+        self.tally_where(player, |_| true)
+    }
+
+    /// The player's results in the games where they played `piece`.
+    pub fn tally_as(&self, player: usize, piece: Piece) -> Tally {
+        self.tally_where(player, |side| side == piece)
+    }
+
+    fn tally_where(&self, player: usize, keep: impl Fn(Piece) -> bool) -> Tally {
+        let mut tally = Tally::default();
+        for &(side, result) in &self.games {
+            let side = if player == 0 { side } else { side.opponent() };
+            if keep(side) {
+                tally.add(result.winner().map(|winner| winner == side));
+            }
+        }
+        tally
+    }
+
+    /// Whether the format says no more games are needed.
+    pub fn is_over(&self) -> bool {
+        let (a, b) = (self.tally(0).wins, self.tally(1).wins);
+        match self.format {
+            Format::BestOf(n) => {
+                let left = n.saturating_sub(self.games_played());
+                left == 0 || a.max(b) > a.min(b) + left
+            }
+            Format::FirstTo(n) => a.max(b) >= n,
+        }
+    }
+
+    /// The player with more wins, if either has.
+    pub fn leader(&self) -> Option<usize> {
+        let (a, b) = (self.tally(0).wins, self.tally(1).wins);
+        match a.cmp(&b) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// One line on how the match went, e.g. "Player wins the match 2-1
+    /// with 1 draw".
+    pub fn summary(&self) -> String {
+        let (a, b) = (self.tally(0), self.tally(1));
+        let draws = match a.draws {
+            1 => "1 draw".to_string(),
+            n => format!("{} draws", n),
+        };
+        match self.leader() {
+            Some(player) => format!(
+                "{} wins the match {}-{} with {}",
+                self.names[player],
+                a.wins.max(b.wins),
+                a.wins.min(b.wins),
+                draws
+            ),
+            None => format!("The match is tied {}-{} with {}", a.wins, b.wins, draws),
+        }
+    }
+}
+
+/// The scoreboard: each player's wins, losses and draws, overall and by
+/// the piece they played.
+impl std::fmt::Display for Series {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let width = self.names.iter().map(String::len).max().unwrap_or(0).max(4);
+        writeln!(
+            f,
+            "{:<width$}  {:>4} {:>4} {:>4}",
+            "",
+            "W",
+            "L",
+            "D",
+            width = width
+        )?;
+        let row = |f: &mut std::fmt::Formatter, label: &str, t: Tally| {
+            writeln!(
+                f,
+                "{:<width$}  {:>4} {:>4} {:>4}",
+                label,
+                t.wins,
+                t.losses,
+                t.draws,
+                width = width
+            )
+        };
+        for player in 0..2 {
+            row(f, &self.names[player], self.tally(player))?;
+            for piece in [Piece::X, Piece::O] {
+                row(f, &format!("  as {}", piece), self.tally_as(player, piece))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,388 @@
+//! A full-screen interface: the board drawn as a grid, cells picked with
+//! the arrow keys or the mouse, a status bar and the move history.
+
+use std::io;
+
+use ratatui::{
+    crossterm::{
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+            MouseButton, MouseEventKind,
+        },
+        execute,
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use crate::game::{format_move, winning_line, GameState, Piece, Position};
+use crate::player::{step_back_to_human, Action, Forfeit, Player};
+use crate::render::{cell_width, Renderer};
+
+/// Rows a cell takes up, including its top border.
+const CELL_HEIGHT: u16 = 2;
+/// Width of the move history panel.
+const HISTORY_WIDTH: u16 = 18;
+
+/// What the screen shows besides the game itself.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct View {
+    /// The selected cell.
+    pub cursor: Position,
+    /// Where the grid was last drawn, for mapping mouse clicks to cells.
+    grid: Rect,
+    /// Columns a cell took up in the last frame, including its left border.
+    cell_width: u16,
+    /// Names the cells and the moves in the history.
+    pub renderer: Renderer,
+}
+
+impl View {
+    /// A view with the cursor in the middle of `state`'s board.
+    pub fn new(state: &GameState) -> Self {
+        let board = state.board();
+        View {
+            cursor: Position {
+                x: board.width() / 2,
+                y: board.height() / 2,
+            },
+            grid: Rect::default(),
+            cell_width: 4,
+            renderer: Renderer::PLAIN,
+        }
+    }
+
+    /// Names cells and moves in `renderer`'s scheme.
+    pub fn with_renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Moves the cursor by `dx` columns and `dy` rows, stopping at the
+    /// edges of the board.
+    pub fn move_cursor(&mut self, state: &GameState, dx: i8, dy: i8) {
+        let board = state.board();
+        let step = |at: u8, by: i8, len: u8| at.saturating_add_signed(by).min(len - 1);
+        self.cursor = Position {
+            x: step(self.cursor.x, dx, board.width()),
+            y: step(self.cursor.y, dy, board.height()),
+        };
+    }
+
+    /// The cell drawn at terminal `column` and `row` in the last frame, if
+    /// any. Borders belong to no cell.
+    pub fn cell_at(&self, state: &GameState, column: u16, row: u16) -> Option<Position> {
+        let grid = self.grid;
+        if column < grid.x || row < grid.y {
+            return None;
+        }
+        let (dx, dy) = (column - grid.x, row - grid.y);
+        if dx % self.cell_width == 0 || dy % CELL_HEIGHT == 0 {
+            return None;
+        }
+        let pos = Position {
+            x: u8::try_from(dx / self.cell_width).ok()?,
+            y: u8::try_from(dy / CELL_HEIGHT).ok()?,
+        };
+        state.board().contains(&pos).then_some(pos)
+    }
+
+    /// Draws the board, the history and `status` into `frame`.
+    pub fn render(&mut self, frame: &mut Frame, state: &GameState, status: &str) {
+        let [main, status_bar] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [board_area, history_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(HISTORY_WIDTH)]).areas(main);
+
+        let board = state.board();
+        let hints = self.renderer.hints(board);
+        let inner = cell_width(&hints);
+        self.cell_width = inner as u16 + 1;
+        let width = board.width() as u16 * self.cell_width + 1;
+        let height = board.height() as u16 * CELL_HEIGHT + 1;
+        self.grid = Rect {
+            x: board_area.x + board_area.width.saturating_sub(width) / 2,
+            y: board_area.y + board_area.height.saturating_sub(height) / 2,
+            width: width.min(board_area.width),
+            height: height.min(board_area.height),
+        };
+        frame.render_widget(
+            Paragraph::new(self.grid_lines(state, &hints, inner)),
+            self.grid,
+        );
+
+        frame.render_widget(
+            Paragraph::new(history_lines(
+                state,
+                &self.renderer,
+                history_area.height.saturating_sub(2),
+            ))
+            .block(Block::bordered().title("Moves")),
+            history_area,
+        );
+        frame.render_widget(
+            Paragraph::new(status).style(Style::new().add_modifier(Modifier::REVERSED)),
+            status_bar,
+        );
+    }
+
+    fn grid_lines(
+        &self,
+        state: &GameState,
+        hints: &[Vec<String>],
+        inner: usize,
+    ) -> Vec<Line<'static>> {
+        let board = state.board();
+        let (width, height) = (board.width() as usize, board.height() as usize);
+        let line = state.result().winner().and_then(|_| winning_line(board));
+        let last = state.moves().last().copied();
+
+        let border = |left: &str, middle: &str, right: &str| {
+            let mut text = left.to_string();
+            for x in 0..width {
+                text.push_str(&"─".repeat(inner));
+                text.push_str(if x + 1 < width { middle } else { right });
+            }
+            Line::from(text)
+        };
+
+        let mut lines = vec![border("┌", "┬", "┐")];
+        for y in 0..height as u8 {
+            let mut spans = vec![Span::raw("│")];
+            for x in 0..width as u8 {
+                let pos = Position { x, y };
+                let (text, mut style) = match board.get(&pos) {
+                    Some(piece) => (
+                        piece.to_string(),
+                        Style::new()
+                            .fg(piece_color(piece))
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    None => (
+                        hints[y as usize][x as usize].clone(),
+                        Style::new().add_modifier(Modifier::DIM),
+                    ),
+                };
+                if line.as_ref().is_some_and(|line| line.contains(&pos)) {
+                    style = style.bg(Color::Green);
+                } else if last == Some(pos) {
+                    style = style.bg(Color::DarkGray);
+                }
+                if pos == self.cursor && !state.result().is_over() {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                spans.push(Span::styled(format!("{:^inner$}", text), style));
+                spans.push(Span::raw("│"));
+            }
+            lines.push(Line::from(spans));
+            if (y as usize) + 1 < height {
+                lines.push(border("├", "┼", "┤"));
+            }
+        }
+        lines.push(border("└", "┴", "┘"));
+        lines
+    }
+}
+
+fn piece_color(piece: Piece) -> Color {
+    match piece {
+        Piece::X => Color::Cyan,
+        Piece::O => Color::Magenta,
+    }
+}
+
+/// The last `rows` moves as numbered lines, e.g. "3. X 5".
+fn history_lines(state: &GameState, renderer: &Renderer, rows: u16) -> Vec<Line<'static>> {
+    let mut game = state.start();
+    let mut lines = Vec::new();
+    for (ply, &pos) in state.moves().iter().enumerate() {
+        let piece = game.to_move();
+        lines.push(Line::from(vec![
+            Span::raw(format!("{:>3}. ", ply + 1)),
+            Span::styled(piece.to_string(), Style::new().fg(piece_color(piece))),
+            Span::raw(format!(" {}", renderer.format_move(game.board(), &pos))),
+        ]));
+        // the moves came from this very game
+        game.play(pos).unwrap();
+    }
+    let skip = lines.len().saturating_sub(rows as usize);
+    lines.split_off(skip)
+}
+
+/// Plays one game between `x` and `o` on the full screen and returns the
+/// game, finished or not: `q` leaves it early. Players that are not human
+/// choose their moves as usual, and forfeit as in `player::run_game`;
+/// people pick cells with the arrow keys or the mouse, or type them in
+/// `renderer`'s scheme, and take moves back with `u` and `r` if
+/// `allow_undo`.
+pub fn run_game(
+    state: GameState,
+    x: &mut dyn Player,
+    o: &mut dyn Player,
+    allow_undo: bool,
+    renderer: &Renderer,
+) -> io::Result<Result<GameState, Forfeit>> {
+    let mut terminal = ratatui::init();
+    // the terminal is put back however the game ends
+    if let Err(e) = execute!(io::stdout(), EnableMouseCapture) {
+        ratatui::restore();
+        return Err(e);
+    }
+    let result = play(&mut terminal, state, x, o, allow_undo, renderer);
+    let _ = execute!(io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+    result
+}
+
+fn play(
+    terminal: &mut DefaultTerminal,
+    mut state: GameState,
+    x: &mut dyn Player,
+    o: &mut dyn Player,
+    allow_undo: bool,
+    renderer: &Renderer,
+) -> io::Result<Result<GameState, Forfeit>> {
+    let humans = (x.is_human(), o.is_human());
+    let is_human = |piece| match piece {
+        Piece::X => humans.0,
+        Piece::O => humans.1,
+    };
+    let mut view = View::new(&state).with_renderer(*renderer);
+    let mut message = String::new();
+
+    while !state.result().is_over() {
+        let piece = state.to_move();
+        let player: &mut dyn Player = match piece {
+            Piece::X => &mut *x,
+            Piece::O => &mut *o,
+        };
+
+        if !player.is_human() {
+            let status = format!(" {} is thinking...", player.name(piece));
+            terminal.draw(|frame| view.render(frame, &state, &status))?;
+            let reason = match player.choose_action(&state) {
+                Action::Play(pos) => match state.play(pos) {
+                    Ok(()) => {
+                        view.cursor = pos;
+                        continue;
+                    }
+                    Err(e) => format!("illegal move {} ({})", format_move(state.board(), &pos), e),
+                },
+                Action::Undo => {
+                    step_back_to_human(&mut state, GameState::undo, is_human);
+                    continue;
+                }
+                Action::Redo => {
+                    step_back_to_human(&mut state, GameState::redo, is_human);
+                    continue;
+                }
+                Action::Resign(reason) => reason,
+            };
+            let status = format!(
+                " {} forfeits: {}. Press any key",
+                player.name(piece),
+                reason
+            );
+            wait_for_key(terminal, &mut view, &state, &status)?;
+            return Ok(Err(Forfeit {
+                piece,
+                reason,
+                game: Box::new(state),
+            }));
+        }
+
+        let keys = if allow_undo {
+            "arrows or mouse to pick, Enter to play, u undo, r redo, q quit"
+        } else {
+            "arrows or mouse to pick, Enter to play, q quit"
+        };
+        let status = match message.as_str() {
+            "" => format!(" {} to move: {}", player.name(piece), keys),
+            message => format!(" {}: {}", player.name(piece), message),
+        };
+        terminal.draw(|frame| view.render(frame, &state, &status))?;
+        message.clear();
+
+        let pick = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Left => {
+                    view.move_cursor(&state, -1, 0);
+                    None
+                }
+                KeyCode::Right => {
+                    view.move_cursor(&state, 1, 0);
+                    None
+                }
+                KeyCode::Up => {
+                    view.move_cursor(&state, 0, -1);
+                    None
+                }
+                KeyCode::Down => {
+                    view.move_cursor(&state, 0, 1);
+                    None
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => Some(view.cursor),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(Ok(state)),
+                KeyCode::Char('u' | 'r') if !allow_undo => {
+                    message = "undo is turned off for this game".to_string();
+                    None
+                }
+                KeyCode::Char('u') => {
+                    if !step_back_to_human(&mut state, GameState::undo, is_human) {
+                        message = "there is nothing to undo".to_string();
+                    }
+                    None
+                }
+                KeyCode::Char('r') => {
+                    if !step_back_to_human(&mut state, GameState::redo, is_human) {
+                        message = "there is nothing to redo".to_string();
+                    }
+                    None
+                }
+                // the move codes work too, e.g. a numpad digit
+                KeyCode::Char(c) => renderer.parse_move(state.board(), &c.to_string()).ok(),
+                _ => None,
+            },
+            Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                view.cell_at(&state, mouse.column, mouse.row)
+            }
+            _ => None,
+        };
+
+        if let Some(pos) = pick {
+            view.cursor = pos;
+            if let Err(e) = state.play(pos) {
+                message = e.to_string();
+            }
+        }
+    }
+
+    let status = match state.result().winner() {
+        Some(Piece::X) => format!(" {} wins! Press any key", x.name(Piece::X)),
+        Some(Piece::O) => format!(" {} wins! Press any key", o.name(Piece::O)),
+        None => " Game over! It's a draw! Press any key".to_string(),
+    };
+    wait_for_key(terminal, &mut view, &state, &status)?;
+    Ok(Ok(state))
+}
+
+/// Shows `status` under the final board until a key or mouse button is
+/// pressed.
+fn wait_for_key(
+    terminal: &mut DefaultTerminal,
+    view: &mut View,
+    state: &GameState,
+    status: &str,
+) -> io::Result<()> {
+    terminal.draw(|frame| view.render(frame, state, status))?;
+    loop {
+        match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => return Ok(()),
+            Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Down(_)) => return Ok(()),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use std::time::Duration;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use ppttt::ai::mcts::{self, Budget, DEFAULT_EXPLORATION};
+use ppttt::ai::solve::{self, SolutionTable, Value};
+use ppttt::ai::tt::{canonical_form, canonical_key};
+use ppttt::ai::{self, pick_move, Clock, Difficulty, Outcome, Selection};
+use ppttt::game::{
+    self, apply_move, is_game_won, is_valid_move, Board, GameResult, GameState, Piece, Position,
+};
+use ppttt::player::{run_game, ComputerPlayer};
+
+/// Builds a 3x3 board from rows top to bottom, `.` for empty cells.
+fn board_from_rows(rows: [&str; 3]) -> Board {
+    let mut board = Board::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, cell) in row.chars().enumerate() {
+            let pos = Position {
+                x: x as u8,
+                y: y as u8,
+            };
+            match cell {
+                'X' => apply_move(&mut board, &pos, Piece::X).unwrap(),
+                'O' => apply_move(&mut board, &pos, Piece::O).unwrap(),
+                _ => {}
+            }
+        }
+    }
+    board
+}
+
+/// Asserts both search variants pick `expected` every time.
+fn assert_picks(board: &Board, piece: Piece, expected: Position) {
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        assert_eq!(ai::pick_best_move(&mut rng, board, piece), expected);
+        assert_eq!(ai::pick_best_move_par(&mut rng, board, piece), expected);
+    }
+}
+
+#[test]
+fn takes_the_win_on_a_large_board() {
+    let mut board = Board::with_size(15, 15, 5).unwrap();
+    for x in 3..7 {
+        apply_move(&mut board, &Position { x, y: 7 }, Piece::X).unwrap();
+    }
+    for x in 3..6 {
+        apply_move(&mut board, &Position { x, y: 9 }, Piece::O).unwrap();
+    }
+    apply_move(&mut board, &Position { x: 7, y: 7 }, Piece::O).unwrap();
+
+    let mut rng = rand::thread_rng();
+    let pos = ai::pick_best_move(&mut rng, &board, Piece::X);
+    assert_eq!(pos, Position { x: 2, y: 7 });
+}
+
+#[test]
+fn opens_in_the_centre_of_an_empty_large_board() {
+    let board = Board::with_size(15, 15, 5).unwrap();
+    let centre = Position { x: 7, y: 7 };
+    let mut rng = rand::thread_rng();
+    assert_eq!(ai::pick_best_move(&mut rng, &board, Piece::X), centre);
+    assert_eq!(
+        Difficulty::Perfect
+            .strategy()
+            .pick_move(&mut rng, &board, Piece::X, Selection::Best),
+        centre
+    );
+
+    // the search itself must stay within its budget too
+    let analysis = ai::analyze(&board, Piece::X);
+    assert_eq!(analysis.len(), 1);
+    assert_eq!(analysis[0].pos, centre);
+}
+
+#[test]
+fn canonical_key_ignores_symmetry() {
+    let mut corner = Board::new();
+    apply_move(&mut corner, &Position { x: 0, y: 0 }, Piece::X).unwrap();
+    apply_move(&mut corner, &Position { x: 1, y: 0 }, Piece::O).unwrap();
+
+    // the same position rotated a quarter turn
+    let mut rotated = Board::new();
+    apply_move(&mut rotated, &Position { x: 2, y: 0 }, Piece::X).unwrap();
+    apply_move(&mut rotated, &Position { x: 2, y: 1 }, Piece::O).unwrap();
+
+    assert_eq!(
+        canonical_key(&corner, Piece::X, Piece::X),
+        canonical_key(&rotated, Piece::X, Piece::X)
+    );
+    assert_ne!(
+        canonical_key(&corner, Piece::X, Piece::X),
+        canonical_key(&corner, Piece::O, Piece::X)
+    );
+
+    // swapping the pieces is a different position
+    let mut swapped = Board::new();
+    apply_move(&mut swapped, &Position { x: 0, y: 0 }, Piece::O).unwrap();
+    apply_move(&mut swapped, &Position { x: 1, y: 0 }, Piece::X).unwrap();
+    assert_ne!(
+        canonical_key(&corner, Piece::X, Piece::X),
+        canonical_key(&swapped, Piece::X, Piece::X)
+    );
+}
+
+#[test]
+fn perfect_self_play_draws() {
+    let mut rng = rand::thread_rng();
+
+    // sequential and parallel search share the table; a bad bound from
+    // either would let the other side win
+    for _ in 0..50 {
+        let mut board = Board::new();
+        let mut piece = Piece::X;
+        let mut parallel = rand::random::<bool>();
+        while !game::is_game_over(&board) {
+            let pos = if parallel {
+                ai::pick_best_move_par(&mut rng, &board, piece)
+            } else {
+                ai::pick_best_move(&mut rng, &board, piece)
+            };
+            apply_move(&mut board, &pos, piece).unwrap();
+            piece = piece.opponent();
+            parallel = !parallel;
+        }
+        assert_eq!(is_game_won(&board), None);
+    }
+}
+
+#[test]
+fn perfect_play_holds_when_o_moves_first() {
+    // O has 7 and 8, so X must take 9
+    let board = board_from_rows(["OO.", ".X.", "..."]);
+    let mut rng = rand::thread_rng();
+    for _ in 0..10 {
+        let pos =
+            Difficulty::Perfect
+                .strategy()
+                .pick_move(&mut rng, &board, Piece::X, Selection::Best);
+        assert_eq!(pos, Position { x: 2, y: 0 });
+    }
+
+    for seed in 0..10 {
+        let start = GameState::with_first(Board::new(), Piece::O);
+        let mut x = ComputerPlayer::minimax().with_seed(seed);
+        let mut o = ComputerPlayer::random().with_seed(seed);
+        let game = run_game(start, &mut x, &mut o, None).unwrap();
+        assert_ne!(game.result(), GameResult::OWins, "seed {}", seed);
+    }
+}
+
+#[test]
+fn takes_an_immediate_win() {
+    // X also has slower forced wins here, but should finish now
+    let board = board_from_rows(["X.O", ".X.", "O.."]);
+    assert_picks(&board, Piece::X, Position { x: 2, y: 2 });
+}
+
+#[test]
+fn blocks_an_immediate_threat() {
+    let board = board_from_rows(["XX.", ".O.", "..."]);
+    assert_picks(&board, Piece::O, Position { x: 2, y: 0 });
+}
+
+#[test]
+fn holds_out_when_lost() {
+    // every move loses, but only blocking the diagonal survives past X's next move
+    let board = board_from_rows(["...", ".X.", ".OX"]);
+    assert_picks(&board, Piece::O, Position { x: 0, y: 0 });
+}
+
+#[test]
+fn sequential_and_parallel_scores_match() {
+    let boards = [
+        Board::new(),
+        board_from_rows(["X..", "...", "..."]),
+        board_from_rows(["X.O", ".X.", "O.."]),
+        board_from_rows(["...", ".X.", ".OX"]),
+    ];
+    for (board, piece) in boards.iter().zip([Piece::X, Piece::O, Piece::X, Piece::O]) {
+        assert_eq!(
+            ai::score_moves(board, piece),
+            ai::score_moves_par(board, piece)
+        );
+    }
+}
+
+#[test]
+fn breaks_ties_at_random() {
+    // every opening move draws, so over enough games more than one gets played
+    let mut rng = rand::thread_rng();
+    let board = Board::new();
+    let openings: Vec<Position> = (0..50)
+        .map(|_| ai::pick_best_move(&mut rng, &board, Piece::X))
+        .collect();
+    assert!(openings.iter().any(|&pos| pos != openings[0]));
+}
+
+#[test]
+fn softmax_keeps_the_best_outcome() {
+    let mut rng = rand::thread_rng();
+    let board = board_from_rows(["X.O", ".X.", "O.."]);
+    for _ in 0..20 {
+        // a huge temperature flattens the weights, but never mixes outcomes
+        let pos = pick_move(
+            &mut rng,
+            &board,
+            Piece::X,
+            Selection::Softmax { temperature: 1e12 },
+        );
+        let mut after = board;
+        apply_move(&mut after, &pos, Piece::X).unwrap();
+        let replies = ai::score_moves(&after, Piece::O);
+        assert!(replies.iter().all(|&(_, score)| score < 0));
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "temperature must be positive")]
+fn softmax_rejects_a_zero_temperature() {
+    let board = board_from_rows(["X.O", ".X.", "O.."]);
+    pick_move(
+        &mut rand::thread_rng(),
+        &board,
+        Piece::X,
+        Selection::Softmax { temperature: 0.0 },
+    );
+}
+
+#[test]
+fn medium_takes_wins_and_blocks() {
+    let mut rng = rand::thread_rng();
+    let medium = Difficulty::Medium.strategy();
+    for _ in 0..20 {
+        let board = board_from_rows(["XX.", ".O.", "..."]);
+        assert_eq!(
+            medium.pick_move(&mut rng, &board, Piece::O, Selection::Best),
+            Position { x: 2, y: 0 }
+        );
+        let board = board_from_rows(["XX.", "OO.", "X.."]);
+        assert_eq!(
+            medium.pick_move(&mut rng, &board, Piece::O, Selection::Best),
+            Position { x: 2, y: 1 }
+        );
+    }
+}
+
+#[test]
+fn depth_limits_beyond_the_game_search_to_the_end() {
+    let mut rng = rand::thread_rng();
+    let deep = ai::Strategy::Minimax {
+        max_depth: Some(u32::MAX),
+        blunder_chance: 0.0,
+    };
+    // against the centre only a corner holds the draw, which takes a full search
+    let board = board_from_rows(["...", ".X.", "..."]);
+    let pos = deep.pick_move(&mut rng, &board, Piece::O, Selection::Best);
+    assert!(
+        [0, 2].contains(&pos.x) && [0, 2].contains(&pos.y),
+        "{:?}",
+        pos
+    );
+}
+
+#[test]
+fn easy_plays_anywhere() {
+    let mut rng = rand::thread_rng();
+    let easy = Difficulty::Easy.strategy();
+    let board = board_from_rows(["XX.", ".O.", "..."]);
+    let picks: Vec<Position> = (0..100)
+        .map(|_| easy.pick_move(&mut rng, &board, Piece::O, Selection::Best))
+        .collect();
+    assert!(picks.iter().all(|pos| is_valid_move(&board, pos)));
+    assert!(picks.iter().any(|&pos| pos != Position { x: 2, y: 0 }));
+}
+
+#[test]
+fn solves_every_classic_position() {
+    let table = solve::classic();
+    assert_eq!(table.len(), 5478);
+
+    let start = table.get(&Board::new()).unwrap();
+    assert_eq!(start.value, Value::Draw);
+    assert_eq!(start.distance, 9);
+    assert_eq!(table.optimal_moves(&Board::new()).len(), 9);
+}
+
+#[test]
+fn minimax_agrees_with_the_solution_table() {
+    let table = solve::classic();
+    let mut positions = vec![Board::new()];
+    let mut seen = std::collections::HashSet::new();
+    let mut piece = Piece::X;
+
+    // walk the tree ply by ply, so every position has the same side to move
+    while !positions.is_empty() {
+        let mut next = Vec::new();
+        for board in positions {
+            if game::is_game_over(&board) || !seen.insert(board) {
+                continue;
+            }
+            let scored = ai::score_moves(&board, piece);
+            let best = scored.iter().map(|&(_, score)| score).max().unwrap();
+            let mut expected: Vec<Position> = scored
+                .iter()
+                .filter(|&&(_, score)| score == best)
+                .map(|&(pos, _)| pos)
+                .collect();
+            let mut optimal = table.optimal_moves(&board);
+            expected.sort_by_key(|pos| (pos.y, pos.x));
+            optimal.sort_by_key(|pos| (pos.y, pos.x));
+            assert_eq!(optimal, expected, "{}", board);
+
+            for pos in game::get_available_moves(&board) {
+                let mut child = board;
+                apply_move(&mut child, &pos, piece).unwrap();
+                next.push(child);
+            }
+        }
+        positions = next;
+        piece = piece.opponent();
+    }
+}
+
+#[test]
+fn solution_tables_round_trip() {
+    let board = Board::with_size(3, 2, 2).unwrap();
+    let table = solve::solve(&board).unwrap();
+    assert_eq!(table.get(&board).unwrap().value, Value::Win);
+
+    let mut bytes = Vec::new();
+    table.write_to(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 12 + 8 * table.len());
+    assert_eq!(SolutionTable::read_from(&mut &bytes[..]).unwrap(), table);
+
+    assert!(SolutionTable::read_from(&mut &b"nope"[..]).is_err());
+    assert!(SolutionTable::read_from(&mut &bytes[..bytes.len() - 3]).is_err());
+    assert!(solve::solve(&Board::with_size(5, 5, 4).unwrap()).is_err());
+}
+
+#[test]
+fn solution_tables_reject_impossible_counts() {
+    let mut header = b"PPTS\x01\x03\x03\x03".to_vec();
+    header.extend(u32::MAX.to_le_bytes());
+    assert!(SolutionTable::read_from(&mut &header[..]).is_err());
+
+    // a count the board allows still needs its records
+    let mut header = b"PPTS\x01\x03\x03\x03".to_vec();
+    header.extend(19683u32.to_le_bytes());
+    assert!(SolutionTable::read_from(&mut &header[..]).is_err());
+}
+
+#[test]
+fn mcts_takes_wins_and_blocks() {
+    let mut rng = rand::thread_rng();
+    let win = board_from_rows(["X.O", ".X.", "O.."]);
+    let threat = board_from_rows(["XX.", ".O.", "..."]);
+    let budgets = [
+        Budget::Iterations(4000),
+        Budget::Time(Duration::from_millis(50)),
+    ];
+
+    for budget in budgets {
+        assert_eq!(
+            mcts::pick_move(&mut rng, &win, Piece::X, budget, DEFAULT_EXPLORATION),
+            Position { x: 2, y: 2 }
+        );
+        assert_eq!(
+            mcts::pick_move_par(&mut rng, &threat, Piece::O, budget, DEFAULT_EXPLORATION, 4),
+            Position { x: 2, y: 0 }
+        );
+    }
+}
+
+#[test]
+fn seeded_parallel_mcts_repeats_itself() {
+    // the trees, and so the playouts each gets, do not depend on the host
+    let board = Board::with_size(5, 5, 4).unwrap();
+    let budget = Budget::Iterations(2000);
+    let pick = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        mcts::pick_move_par(&mut rng, &board, Piece::X, budget, DEFAULT_EXPLORATION, 8)
+    };
+    for seed in 0..5 {
+        assert_eq!(pick(seed), pick(seed));
+    }
+}
+
+#[test]
+fn deepening_matches_the_full_search() {
+    let board = board_from_rows(["X..", ".O.", "..X"]);
+    let clock = Clock::with_budget(Duration::from_secs(60));
+    let mut timed = ai::score_moves_timed(&board, Piece::O, &clock);
+    let mut full = ai::score_moves(&board, Piece::O);
+    timed.sort_by_key(|&(pos, _)| (pos.y, pos.x));
+    full.sort_by_key(|&(pos, _)| (pos.y, pos.x));
+    assert_eq!(timed, full);
+    assert!(!clock.is_stopped());
+}
+
+#[test]
+fn deepening_answers_within_its_budget() {
+    let mut board = Board::with_size(15, 15, 5).unwrap();
+    for x in 3..7 {
+        apply_move(&mut board, &Position { x, y: 7 }, Piece::X).unwrap();
+    }
+    for x in 3..7 {
+        apply_move(&mut board, &Position { x, y: 9 }, Piece::O).unwrap();
+    }
+
+    let mut rng = rand::thread_rng();
+    let start = std::time::Instant::now();
+    let pos = ai::pick_move_timed_par(
+        &mut rng,
+        &board,
+        Piece::X,
+        Duration::from_millis(200),
+        Selection::Best,
+    );
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    apply_move(&mut board, &pos, Piece::X).unwrap();
+    assert_eq!(is_game_won(&board), Some(Piece::X));
+}
+
+#[test]
+fn a_cancelled_search_still_scores_every_move() {
+    let board = Board::with_size(7, 7, 4).unwrap();
+    let clock = Clock::unlimited();
+    clock.cancel();
+    let scored = ai::score_moves_timed_par(&board, Piece::X, &clock);
+    assert_eq!(scored.len(), ai::candidate_moves(&board).len());
+}
+
+#[test]
+fn symmetries_map_moves_back_and_forth() {
+    let board = board_from_rows(["X..", "...", ".O."]);
+    let rotated = board_from_rows(["..X", "O..", "..."]);
+    let (key, symmetry) = canonical_form(&board, Piece::X, Piece::X);
+    let (rotated_key, rotated_symmetry) = canonical_form(&rotated, Piece::X, Piece::X);
+    assert_eq!(key, rotated_key);
+
+    for pos in game::get_available_moves(&board) {
+        let cell = symmetry.apply(&board, pos);
+        assert_eq!(symmetry.invert(&board, cell), pos);
+        // the same canonical cell is the matching move on the rotated board
+        let turned = rotated_symmetry.invert(&rotated, cell);
+        assert!(is_valid_move(&rotated, &turned));
+    }
+}
+
+#[test]
+fn analysis_classifies_every_move() {
+    let analysis = ai::analyze(&Board::new(), Piece::X);
+    assert_eq!(analysis.len(), 9);
+    for a in &analysis {
+        assert_eq!(a.outcome, Outcome::Draw);
+        assert_eq!(a.distance, Some(9));
+        assert_eq!(a.pv[0], a.pos);
+    }
+
+    let board = board_from_rows(["X.O", ".X.", "O.."]);
+    let analysis = ai::analyze(&board, Piece::X);
+    let best = &analysis[0];
+    assert_eq!(best.pos, Position { x: 2, y: 2 });
+    assert_eq!(best.outcome, Outcome::Win);
+    assert_eq!(best.distance, Some(1));
+    assert_eq!(best.pv, vec![best.pos]);
+    assert!(analysis.windows(2).all(|w| w[0].score >= w[1].score));
+
+    // every line is playable from the position
+    for a in &analysis {
+        let mut line = board;
+        let mut piece = Piece::X;
+        for pos in &a.pv {
+            apply_move(&mut line, pos, piece).unwrap();
+            piece = piece.opponent();
+        }
+    }
+}
+
+#[test]
+fn analysis_sees_forced_losses() {
+    let board = board_from_rows(["...", ".X.", ".OX"]);
+    let analysis = ai::analyze(&board, Piece::O);
+    assert_eq!(analysis[0].pos, Position { x: 0, y: 0 });
+    for a in &analysis {
+        assert_eq!(a.outcome, Outcome::Loss);
+        assert!(a.distance.unwrap() >= 2);
+    }
+    assert_eq!(analysis[0].distance, Some(4));
+}
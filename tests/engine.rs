@@ -0,0 +1,150 @@
+use std::io::Cursor;
+use std::process;
+use std::time::Duration;
+
+use ppttt::engine::{Command, Engine, ExternalEngine};
+use ppttt::game::{move_code_to_position, Board, GameResult, GameState, Piece, Position};
+use ppttt::player::{run_game, ComputerPlayer, Player};
+
+fn code(code: &str) -> Position {
+    move_code_to_position(code).unwrap()
+}
+
+#[test]
+fn commands_read_back_what_they_write() {
+    let mut game = GameState::with_first(Board::new(), Piece::O);
+    game.play(code("5")).unwrap();
+    game.play(code("1")).unwrap();
+    let commands = [
+        Command::NewGame,
+        Command::Position(game),
+        Command::Position(GameState::new(Board::with_size(4, 4, 3).unwrap())),
+        Command::Go { movetime: None },
+        Command::Go {
+            movetime: Some(Duration::from_millis(250)),
+        },
+        Command::IsReady,
+        Command::Quit,
+    ];
+    for command in commands {
+        assert_eq!(command.to_string().parse(), Ok(command));
+    }
+
+    assert_eq!(
+        Command::Position("XO./.../... x".parse().unwrap()).to_string(),
+        "position XO./.../... x"
+    );
+    assert!("position .../.../... x moves 5 5"
+        .parse::<Command>()
+        .is_err());
+    assert!("go movetime soon".parse::<Command>().is_err());
+}
+
+#[test]
+fn engine_answers_go_with_its_line_and_move() {
+    let input = "isready\nposition XX./OO./... x\ngo\nquit\ngo\n";
+    let mut output = Vec::new();
+    Engine::new().run(Cursor::new(input), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines.len(),
+        3,
+        "nothing is answered after quit: {:?}",
+        lines
+    );
+    assert_eq!(lines[0], "readyok");
+    assert!(lines[1].starts_with("info score ") && lines[1].ends_with(" pv 9"));
+    assert_eq!(lines[2], "bestmove 9");
+}
+
+#[test]
+fn engine_reports_bad_commands_and_finished_games() {
+    let mut engine = Engine::new().with_seed(7);
+    let over: Command = "position XXX/OO./... o".parse().unwrap();
+    assert!(engine.respond(&over).is_empty());
+    assert_eq!(
+        engine.respond(&Command::Go { movetime: None }),
+        ["info string the game is over", "bestmove none"]
+    );
+
+    let mut output = Vec::new();
+    engine.run(Cursor::new("castle\n"), &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "info string unknown command: castle\n"
+    );
+}
+
+#[test]
+fn external_engines_play_against_the_builtin_one() {
+    let mut command = process::Command::new(env!("CARGO_BIN_EXE_ppttt"));
+    command.arg("engine");
+    let mut engine = ExternalEngine::spawn(command).unwrap();
+    assert_eq!(engine.name(Piece::X), "ppttt X");
+
+    // perfect play on both sides draws, whoever starts
+    for first in [Piece::X, Piece::O] {
+        let start = GameState::with_first(Board::new(), first);
+        let game = run_game(start, &mut engine, &mut ComputerPlayer::minimax(), None).unwrap();
+        assert_eq!(game.result(), GameResult::Draw);
+    }
+}
+
+#[test]
+fn spawning_fails_if_the_program_does_not_speak_the_protocol() {
+    let mut command = process::Command::new(env!("CARGO_BIN_EXE_ppttt"));
+    command.arg("--version");
+    assert!(ExternalEngine::spawn(command).is_err());
+}
+
+/// An engine that answers `isready`, and `go` as `on_go` says, written as
+/// a shell script.
+fn scripted_engine(on_go: &str) -> ExternalEngine {
+    let script = format!(
+        "while read line; do case \"$line\" in isready) echo readyok;; go*) {};; quit) exit;; esac; done",
+        on_go
+    );
+    let mut command = process::Command::new("sh");
+    command.args(["-c", &script]);
+    ExternalEngine::spawn(command).unwrap()
+}
+
+#[test]
+fn engines_that_answer_illegal_moves_forfeit() {
+    let mut engine = scripted_engine("echo bestmove 5");
+    let mut taken = GameState::new(Board::new());
+    taken.play(code("5")).unwrap();
+    let err = engine.best_move(&taken).unwrap_err();
+    assert!(err.to_string().contains("already taken"), "{}", err);
+
+    let mut o = ComputerPlayer::minimax().with_seed(1);
+    let forfeit = run_game(GameState::new(Board::new()), &mut engine, &mut o, None).unwrap_err();
+    assert_eq!(forfeit.piece, Piece::X);
+    assert_eq!(forfeit.game.moves().len(), 2);
+
+    let mut engine = scripted_engine("echo bestmove 0");
+    assert!(engine.best_move(&GameState::new(Board::new())).is_err());
+}
+
+#[test]
+fn engines_that_exit_forfeit() {
+    let mut engine = scripted_engine("exit 1");
+    let mut x = ComputerPlayer::minimax().with_seed(1);
+    let forfeit = run_game(GameState::new(Board::new()), &mut x, &mut engine, None).unwrap_err();
+    assert_eq!(forfeit.piece, Piece::O);
+    assert_eq!(forfeit.result(), GameResult::XWins);
+    assert!(forfeit.reason.contains("exited"), "{}", forfeit.reason);
+}
+
+#[test]
+fn engines_that_stop_answering_forfeit() {
+    let mut engine = scripted_engine("true").with_movetime(Duration::from_millis(50));
+    let mut o = ComputerPlayer::minimax().with_seed(1);
+    let forfeit = run_game(GameState::new(Board::new()), &mut engine, &mut o, None).unwrap_err();
+    assert_eq!(forfeit.piece, Piece::X);
+    assert!(forfeit.reason.contains("in time"), "{}", forfeit.reason);
+
+    // it was stopped, so later questions fail at once
+    assert!(engine.best_move(&GameState::new(Board::new())).is_err());
+}
@@ -0,0 +1,300 @@
+use ppttt::game::{
+    apply_move, format_move, get_available_moves, is_game_won, parse_move, winning_line, Board,
+    GameResult, GameState, MoveError, Piece, Position, PositionError, Scheme,
+};
+
+#[test]
+fn runs_do_not_wrap_between_rows() {
+    let mut board = Board::with_size(4, 4, 4).unwrap();
+    // the last two cells of row 0 and the first two of row 1
+    for (x, y) in [(2, 0), (3, 0), (0, 1), (1, 1)] {
+        apply_move(&mut board, &Position { x, y }, Piece::X).unwrap();
+    }
+    assert_eq!(is_game_won(&board), None);
+
+    apply_move(&mut board, &Position { x: 0, y: 0 }, Piece::X).unwrap();
+    apply_move(&mut board, &Position { x: 1, y: 0 }, Piece::X).unwrap();
+    assert_eq!(is_game_won(&board), Some(Piece::X));
+}
+
+#[test]
+fn gomoku_diagonals() {
+    let mut board = Board::with_size(15, 15, 5).unwrap();
+    for i in 0..4 {
+        apply_move(
+            &mut board,
+            &Position {
+                x: 14 - i,
+                y: 10 + i,
+            },
+            Piece::O,
+        )
+        .unwrap();
+    }
+    assert_eq!(is_game_won(&board), None);
+
+    apply_move(&mut board, &Position { x: 10, y: 14 }, Piece::O).unwrap();
+    assert_eq!(is_game_won(&board), Some(Piece::O));
+}
+
+#[test]
+fn coordinate_moves_round_trip() {
+    let board = Board::with_size(15, 15, 5).unwrap();
+    for pos in get_available_moves(&board) {
+        assert_eq!(parse_move(&board, &format_move(&board, &pos)), Ok(pos));
+    }
+    assert_eq!(parse_move(&board, "a1"), Ok(Position { x: 0, y: 14 }));
+    assert_eq!(parse_move(&board, "p1"), Err(MoveError::OutOfBounds));
+    assert_eq!(parse_move(&board, "a16"), Err(MoveError::OutOfBounds));
+    assert_eq!(parse_move(&board, "1a"), Err(MoveError::BadSyntax));
+    assert_eq!(parse_move(&board, ""), Err(MoveError::BadSyntax));
+}
+
+#[test]
+fn game_state_alternates_and_ends() {
+    let mut game = GameState::new(Board::new());
+    assert_eq!(game.to_move(), Piece::X);
+    for (x, y) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+        game.play(Position { x, y }).unwrap();
+    }
+    assert_eq!(game.to_move(), Piece::X);
+    assert_eq!(game.result(), GameResult::Ongoing);
+
+    assert_eq!(game.play(Position { x: 1, y: 1 }), Err(MoveError::Occupied));
+    assert_eq!(
+        game.play(Position { x: 3, y: 0 }),
+        Err(MoveError::OutOfBounds)
+    );
+
+    game.play(Position { x: 2, y: 0 }).unwrap();
+    assert_eq!(game.result(), GameResult::XWins);
+    assert_eq!(game.play(Position { x: 2, y: 2 }), Err(MoveError::GameOver));
+    assert_eq!(game.moves().len(), 5);
+}
+
+#[test]
+fn game_state_undo_restores_everything() {
+    let mut game = GameState::new(Board::new());
+    let before = game.clone();
+    game.play(Position { x: 1, y: 1 }).unwrap();
+    game.play(Position { x: 0, y: 0 }).unwrap();
+
+    assert_eq!(game.undo(), Some(Position { x: 0, y: 0 }));
+    assert_eq!(game.to_move(), Piece::O);
+    assert_eq!(game.undo(), Some(Position { x: 1, y: 1 }));
+    assert_eq!(game, before);
+    assert_eq!(game.undo(), None);
+
+    // undoing a winning move reopens the game
+    for (x, y) in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)] {
+        game.play(Position { x, y }).unwrap();
+    }
+    assert_eq!(game.result(), GameResult::XWins);
+    game.undo();
+    assert_eq!(game.result(), GameResult::Ongoing);
+    assert_eq!(game.to_move(), Piece::X);
+}
+
+#[test]
+fn a_full_board_without_a_line_is_a_draw() {
+    let mut game = GameState::new(Board::new());
+    // X O X / X O O / O X X
+    for code in ["7", "8", "9", "5", "4", "6", "2", "1", "3"] {
+        game.play(parse_move(game.board(), code).unwrap()).unwrap();
+    }
+    assert_eq!(game.result(), GameResult::Draw);
+}
+
+#[test]
+fn move_errors_are_typed() {
+    use ppttt::game::move_code_to_position;
+
+    assert_eq!(move_code_to_position("x"), Err(MoveError::BadSyntax));
+    assert_eq!(move_code_to_position("55"), Err(MoveError::BadSyntax));
+    assert_eq!(move_code_to_position("0"), Err(MoveError::OutOfBounds));
+
+    let mut board = Board::new();
+    let centre = Position { x: 1, y: 1 };
+    apply_move(&mut board, &centre, Piece::X).unwrap();
+    assert_eq!(
+        apply_move(&mut board, &centre, Piece::O),
+        Err(MoveError::Occupied)
+    );
+    assert_eq!(board.get(&centre), Some(Piece::X));
+    assert_eq!(
+        apply_move(&mut board, &Position { x: 0, y: 3 }, Piece::O),
+        Err(MoveError::OutOfBounds)
+    );
+
+    let mut game = GameState::new(Board::new());
+    assert_eq!(game.play_as(Piece::O, centre), Err(MoveError::WrongTurn));
+    assert_eq!(game.play_as(Piece::X, centre), Ok(()));
+}
+
+#[test]
+fn positions_display_without_panicking() {
+    assert_eq!(Position { x: 0, y: 2 }.to_string(), "1");
+    assert_eq!(Position { x: 7, y: 4 }.to_string(), "(7, 4)");
+}
+
+#[test]
+fn positions_round_trip_through_notation() {
+    let mut game = GameState::new(Board::new());
+    for code in ["5", "1", "9"] {
+        game.play(parse_move(game.board(), code).unwrap()).unwrap();
+    }
+    assert_eq!(game.notation(), "..X/.X./O.. o");
+
+    let loaded: GameState = game.notation().parse().unwrap();
+    assert_eq!(loaded.board(), game.board());
+    assert_eq!(loaded.to_move(), Piece::O);
+    assert!(loaded.moves().is_empty());
+
+    // a game O started has O a piece ahead with X to move, or level with O
+    // to move
+    let round_trip = |game: &GameState| {
+        let loaded: GameState = game.notation().parse().unwrap();
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.to_move(), game.to_move());
+    };
+    let mut o_first = GameState::with_first(Board::new(), Piece::O);
+    assert_eq!(o_first.notation(), ".../.../... o");
+    round_trip(&o_first);
+    for (code, notation) in [
+        ("5", ".../.O./... x"),
+        ("1", ".../.O./X.. o"),
+        ("9", "..O/.O./X.. x"),
+    ] {
+        o_first
+            .play(parse_move(o_first.board(), code).unwrap())
+            .unwrap();
+        assert_eq!(o_first.notation(), notation);
+        round_trip(&o_first);
+    }
+
+    let wide: GameState = "...X.../...O... x 4".parse().unwrap();
+    assert_eq!((wide.board().width(), wide.board().height()), (7, 2));
+    assert_eq!(wide.board().k(), 4);
+    assert_eq!(wide.board().get(&Position { x: 3, y: 0 }), Some(Piece::X));
+    assert_eq!(wide.notation(), "...X.../...O... x 4");
+
+    let won: GameState = "XXX/OO./... o".parse().unwrap();
+    assert_eq!(won.result(), GameResult::XWins);
+}
+
+#[test]
+fn bad_positions_are_rejected() {
+    let parse = |s: &str| s.parse::<GameState>().unwrap_err();
+    assert_eq!(parse("XO./.X./..O"), PositionError::BadSyntax);
+    assert_eq!(parse("XO./.Z./..O x"), PositionError::BadSyntax);
+    assert_eq!(parse("XO./.X/..O x"), PositionError::RaggedRows);
+    assert!(matches!(parse("... x 4"), PositionError::BadBoard(_)));
+    assert_eq!(parse("XX./.X./..O o"), PositionError::BadCounts);
+    assert_eq!(parse("XO./.X./... x"), PositionError::WrongSide);
+    assert_eq!(parse("XO./.O./... o"), PositionError::WrongSide);
+    assert_eq!(parse("XXX/OOO/X.. o"), PositionError::BothWon);
+    assert_eq!(parse("XXX/OO./O.. x"), PositionError::PlayedAfterWin);
+}
+
+#[test]
+fn game_state_redo_replays_undone_moves() {
+    let mut game = GameState::new(Board::new());
+    game.play(Position { x: 1, y: 1 }).unwrap();
+    game.play(Position { x: 0, y: 0 }).unwrap();
+    let played = game.clone();
+
+    game.undo();
+    game.undo();
+    assert_eq!(game.redo(), Some(Position { x: 1, y: 1 }));
+    assert_eq!(game.redo(), Some(Position { x: 0, y: 0 }));
+    assert_eq!(game.redo(), None);
+    assert_eq!(game, played);
+
+    // a new move replaces the moves that were taken back
+    game.undo();
+    game.play(Position { x: 2, y: 2 }).unwrap();
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.start(), GameState::new(Board::new()));
+}
+
+#[test]
+fn either_side_can_move_first() {
+    let mut game = GameState::with_first(Board::new(), Piece::O);
+    assert_eq!(game.to_move(), Piece::O);
+    game.play(Position { x: 1, y: 1 }).unwrap();
+    assert_eq!(game.board().get(&Position { x: 1, y: 1 }), Some(Piece::O));
+    assert_eq!(game.to_move(), Piece::X);
+    assert_eq!(game.start().to_move(), Piece::O);
+}
+
+#[test]
+fn winning_line_finds_the_cells() {
+    let state: GameState = "XXX/OO./... o".parse().unwrap();
+    assert_eq!(
+        winning_line(state.board()),
+        Some(vec![
+            Position { x: 0, y: 0 },
+            Position { x: 1, y: 0 },
+            Position { x: 2, y: 0 }
+        ])
+    );
+
+    let diagonal: GameState = "O.X/.X./XO. o".parse().unwrap();
+    let line = winning_line(diagonal.board()).unwrap();
+    assert_eq!(line.len(), 3);
+    assert!(line.contains(&Position { x: 2, y: 0 }));
+    assert!(line.contains(&Position { x: 0, y: 2 }));
+
+    assert_eq!(winning_line(&Board::new()), None);
+}
+
+#[test]
+fn input_schemes_read_what_they_write() {
+    let classic = Board::new();
+    let top_right = Position { x: 2, y: 0 };
+    for (scheme, code, range) in [
+        (Scheme::Numpad, "9", "[1..9]"),
+        (Scheme::Phone, "3", "[1..9]"),
+        (Scheme::Chess, "c3", "[a1..c3]"),
+        (Scheme::RowCol, "1,3", "[1,1..3,3]"),
+    ] {
+        assert_eq!(scheme.format(&classic, &top_right), code);
+        assert_eq!(scheme.parse(&classic, code), Ok(top_right));
+        assert_eq!(scheme.describe(&classic), range);
+    }
+    assert_eq!(
+        Scheme::Phone.parse(&classic, "1"),
+        Ok(Position { x: 0, y: 0 })
+    );
+    assert_eq!(
+        Scheme::RowCol.parse(&classic, "4,1"),
+        Err(MoveError::OutOfBounds)
+    );
+    assert_eq!(
+        Scheme::RowCol.parse(&classic, "2"),
+        Err(MoveError::BadSyntax)
+    );
+    assert_eq!(
+        Scheme::Phone.parse(&classic, "10"),
+        Err(MoveError::OutOfBounds)
+    );
+
+    // the numbered schemes keep counting on larger boards
+    let wide = Board::with_size(5, 4, 4).unwrap();
+    for scheme in [Scheme::Numpad, Scheme::Phone, Scheme::Chess, Scheme::RowCol] {
+        for pos in get_available_moves(&wide) {
+            let code = scheme.format(&wide, &pos);
+            assert_eq!(scheme.parse(&wide, &code), Ok(pos), "{:?} {}", scheme, code);
+        }
+    }
+    assert_eq!(Scheme::Numpad.format(&wide, &Position { x: 4, y: 0 }), "20");
+    assert_eq!(Scheme::Phone.format(&wide, &Position { x: 4, y: 0 }), "5");
+    assert_eq!(Scheme::standard(&wide), Scheme::Chess);
+    assert_eq!(parse_move(&wide, "e4"), Scheme::Chess.parse(&wide, "e4"));
+
+    // cells off the board have no name
+    for scheme in [Scheme::Numpad, Scheme::Phone, Scheme::Chess, Scheme::RowCol] {
+        assert_eq!(scheme.format(&classic, &Position { x: 9, y: 9 }), "(9, 9)");
+        assert_eq!(scheme.format(&wide, &Position { x: 200, y: 0 }), "(200, 0)");
+    }
+}
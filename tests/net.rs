@@ -0,0 +1,92 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use ppttt::game::{Board, GameResult, GameState, Position};
+use ppttt::net::{host_game, play_remote};
+use ppttt::player::ScriptedPlayer;
+
+/// Two connected client streams and the server's game between them.
+fn start_game() -> (TcpStream, TcpStream, thread::JoinHandle<GameState>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let x = TcpStream::connect(addr).unwrap();
+    let (x_seat, _) = listener.accept().unwrap();
+    let o = TcpStream::connect(addr).unwrap();
+    let (o_seat, _) = listener.accept().unwrap();
+    let server =
+        thread::spawn(move || host_game(GameState::new(Board::new()), x_seat, o_seat).unwrap());
+    (x, o, server)
+}
+
+fn expect(reader: &mut impl BufRead, line: &str) {
+    let mut got = String::new();
+    reader.read_line(&mut got).unwrap();
+    assert_eq!(got.trim_end(), line);
+}
+
+#[test]
+fn clients_play_a_game_through_the_server() {
+    let (x, o, server) = start_game();
+    let o_client = thread::spawn(move || {
+        let mut player = ScriptedPlayer::new([Position { x: 0, y: 1 }, Position { x: 2, y: 2 }]);
+        play_remote(o, &mut player, None).unwrap()
+    });
+    let mut player = ScriptedPlayer::new([
+        Position { x: 0, y: 0 },
+        Position { x: 1, y: 0 },
+        Position { x: 2, y: 0 },
+    ]);
+    let seen_by_x = play_remote(x, &mut player, None).unwrap();
+    let seen_by_o = o_client.join().unwrap();
+    let game = server.join().unwrap();
+
+    assert_eq!(game.result(), GameResult::XWins);
+    assert_eq!(seen_by_x.board(), game.board());
+    assert_eq!(seen_by_o.board(), game.board());
+}
+
+#[test]
+fn server_refuses_illegal_moves() {
+    let (x, o, server) = start_game();
+    let mut x_lines = BufReader::new(x.try_clone().unwrap());
+    let mut o_lines = BufReader::new(o.try_clone().unwrap());
+    let (mut x, mut o) = (x, o);
+
+    expect(&mut x_lines, "HELLO x");
+    expect(&mut x_lines, "BOARD .../.../... x");
+    expect(&mut x_lines, "YOURMOVE");
+    expect(&mut o_lines, "HELLO o");
+    expect(&mut o_lines, "BOARD .../.../... x");
+
+    writeln!(o, "MOVE 5").unwrap();
+    expect(&mut o_lines, "ERROR not your turn");
+    writeln!(x, "MOVE 0").unwrap();
+    expect(&mut x_lines, "ERROR that cell is not on the board");
+    expect(&mut x_lines, "YOURMOVE");
+    writeln!(x, "MOVE 5").unwrap();
+    expect(&mut x_lines, "MOVED x 5");
+    expect(&mut x_lines, "BOARD .../.X./... o");
+    expect(&mut o_lines, "MOVED x 5");
+    expect(&mut o_lines, "BOARD .../.X./... o");
+    expect(&mut o_lines, "YOURMOVE");
+    writeln!(o, "MOVE 5").unwrap();
+    expect(&mut o_lines, "ERROR that cell is already taken");
+    expect(&mut o_lines, "YOURMOVE");
+
+    writeln!(o, "QUIT").unwrap();
+    expect(&mut x_lines, "BYE opponent left");
+    let game = server.join().unwrap();
+    assert!(!game.result().is_over());
+    assert_eq!(game.moves().len(), 1);
+}
+
+#[test]
+fn a_disconnect_ends_the_game_for_the_other_side() {
+    let (x, o, server) = start_game();
+    drop(o);
+    let mut player = ScriptedPlayer::new([Position { x: 1, y: 1 }]);
+    let game = play_remote(x, &mut player, None).unwrap();
+    assert!(!game.result().is_over());
+    assert!(!server.join().unwrap().result().is_over());
+}
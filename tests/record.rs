@@ -0,0 +1,124 @@
+use ppttt::game::{parse_move, Board, GameResult, GameState, MoveError, Piece, Position};
+use ppttt::record::{GameRecord, RecordError};
+
+fn play(mut game: GameState, moves: &[&str]) -> GameState {
+    for input in moves {
+        game.play(parse_move(game.board(), input).unwrap()).unwrap();
+    }
+    game
+}
+
+#[test]
+fn records_round_trip() {
+    let game = play(GameState::new(Board::new()), &["5", "1", "2", "3", "8"]);
+    let record = GameRecord::new(&game, "Player X", "Computer");
+    let text = record.to_string();
+    assert!(text.contains("[Result \"1-0\"]"));
+    assert!(text.contains("[Variant \"3,3,3\"]"));
+    assert!(text.ends_with("1. 5 1 2. 2 3 3. 8 1-0\n"));
+
+    let loaded: GameRecord = text.parse().unwrap();
+    assert_eq!(loaded, record);
+    assert_eq!(loaded.header("O"), Some("Computer"));
+    assert_eq!(loaded.game(), game);
+}
+
+#[test]
+fn records_remember_when_o_moved_first() {
+    let game = play(
+        GameState::with_first(Board::new(), Piece::O),
+        &["5", "1", "9"],
+    );
+    let text = GameRecord::new(&game, "Computer O", "Player X").to_string();
+    assert!(text.contains("[First \"O\"]"));
+    assert!(!text.contains("[Position"));
+
+    let loaded: GameRecord = text.parse().unwrap();
+    assert_eq!(loaded.start().to_move(), Piece::O);
+    assert_eq!(loaded.game(), game);
+
+    let bad = text.replace("[First \"O\"]", "[First \"Z\"]");
+    assert_eq!(
+        bad.parse::<GameRecord>(),
+        Err(RecordError::BadFirst("Z".to_string()))
+    );
+}
+
+#[test]
+fn records_keep_the_starting_position() {
+    let start: GameState = ".../.X./... o".parse().unwrap();
+    let game = play(start.clone(), &["9", "1"]);
+    let record = GameRecord::new(&game, "A", "B");
+    let text = record.to_string();
+    assert!(text.contains("[Position \".../.X./... o\"]"));
+    assert!(text.ends_with("1... 9 2. 1 *\n"));
+
+    let loaded: GameRecord = text.parse().unwrap();
+    assert_eq!(loaded.start(), &start);
+    assert_eq!(loaded.game().result(), GameResult::Ongoing);
+
+    let wide = play(
+        GameState::new(Board::with_size(5, 4, 4).unwrap()),
+        &["c2", "c3"],
+    );
+    let loaded: GameRecord = GameRecord::new(&wide, "A", "B")
+        .to_string()
+        .parse()
+        .unwrap();
+    assert_eq!(loaded.game().board(), wide.board());
+}
+
+#[test]
+fn records_keep_the_result_of_a_forfeit() {
+    let game = play(GameState::new(Board::new()), &["5", "1"]);
+    let record = GameRecord::new(&game, "bot", "Computer O")
+        .with_result(GameResult::OWins)
+        .with_header("Termination", "X forfeits: bot exited");
+    let text = record.to_string();
+    assert!(text.contains("[Result \"0-1\"]"));
+    assert!(text.contains("[Termination \"X forfeits: bot exited\"]"));
+    assert!(text.ends_with("1. 5 1 0-1\n"));
+
+    let loaded: GameRecord = text.parse().unwrap();
+    assert_eq!(loaded, record);
+    assert_eq!(loaded.result(), GameResult::OWins);
+    assert_eq!(loaded.game().result(), GameResult::Ongoing);
+}
+
+#[test]
+fn bad_records_are_rejected() {
+    let parse = |s: &str| s.parse::<GameRecord>().unwrap_err();
+    assert!(matches!(parse("[X Player]\n"), RecordError::BadHeader(_)));
+    assert!(matches!(
+        parse("[Variant \"3,3\"]\n"),
+        RecordError::BadVariant(_)
+    ));
+    assert!(matches!(
+        parse("[Position \"XX./.../... x\"]\n"),
+        RecordError::BadPosition(_)
+    ));
+    assert_eq!(
+        parse("1. 5 5 *"),
+        RecordError::BadMove {
+            ply: 2,
+            error: MoveError::Occupied
+        }
+    );
+}
+
+#[test]
+fn records_are_saved_to_a_directory() {
+    let dir = std::env::temp_dir().join(format!("ppttt-records-{}", std::process::id()));
+    let game = play(GameState::new(Board::new()), &["5", "1"]);
+    let record = GameRecord::new(&game, "A", "B");
+
+    let first = record.save_in(&dir).unwrap();
+    let second = record.save_in(&dir).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(GameRecord::load(&first).unwrap(), record);
+    assert_eq!(
+        GameRecord::load(&second).unwrap().moves(),
+        &[Position { x: 1, y: 1 }, Position { x: 0, y: 2 }]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
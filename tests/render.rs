@@ -0,0 +1,92 @@
+use ppttt::game::{Board, GameState, Position, Scheme};
+use ppttt::render::{Renderer, Style};
+
+fn render(style: Style, color: bool, notation: &str) -> String {
+    let state: GameState = notation.parse().unwrap();
+    Renderer {
+        style,
+        color,
+        scheme: None,
+    }
+    .render(state.board())
+}
+
+#[test]
+fn plain_matches_the_board_display() {
+    for notation in ["X../.O./... x", ".X../..../O... x", "XXX/OO./... o"] {
+        let state: GameState = notation.parse().unwrap();
+        assert_eq!(
+            Renderer::PLAIN.render(state.board()),
+            state.board().to_string()
+        );
+    }
+    assert_eq!(Board::new().to_string(), "789\n456\n123\n");
+}
+
+#[test]
+fn box_drawing_grid() {
+    assert_eq!(
+        render(Style::Unicode, false, "X../.O./... x"),
+        "┌───┬───┬───┐\n\
+         │ X │ 8 │ 9 │\n\
+         ├───┼───┼───┤\n\
+         │ 4 │ O │ 6 │\n\
+         ├───┼───┼───┤\n\
+         │ 1 │ 2 │ 3 │\n\
+         └───┴───┴───┘\n"
+    );
+}
+
+#[test]
+fn the_winning_line_stands_out() {
+    assert_eq!(
+        render(Style::Ascii, false, "XXX/OO./... o"),
+        "[X]|[X]|[X]\n---+---+---\n O | O | 6\n---+---+---\n 1 | 2 | 3\n"
+    );
+
+    let colored = render(Style::Ascii, true, "XXX/OO./... o");
+    // green behind the line, dimmed hints, and every color reset
+    assert_eq!(colored.matches("\x1b[1;30;42mX\x1b[0m").count(), 3);
+    assert!(colored.contains("\x1b[2m6\x1b[0m"));
+    assert!(!colored.contains("[X]"));
+    assert_eq!(colored.matches("\x1b[0m").count(), 9);
+}
+
+#[test]
+fn large_boards_show_coordinates_in_the_cells() {
+    let state = GameState::new(Board::with_size(10, 10, 5).unwrap());
+    let text = Renderer {
+        style: Style::Ascii,
+        color: false,
+        scheme: None,
+    }
+    .render(state.board());
+    let first = text.lines().next().unwrap();
+    assert!(first.starts_with("a10|b10|"));
+    assert_eq!(
+        text.lines().last().unwrap(),
+        "a1 |b1 |c1 |d1 |e1 |f1 |g1 |h1 |i1 |j1"
+    );
+}
+
+#[test]
+fn hints_follow_the_input_scheme() {
+    let state: GameState = "X../.O./... x".parse().unwrap();
+    let phone = Renderer {
+        style: Style::Plain,
+        color: false,
+        scheme: Some(Scheme::Phone),
+    };
+    assert_eq!(phone.render(state.board()), "X23\n4O6\n789\n");
+    assert_eq!(
+        phone.format_move(state.board(), &Position { x: 2, y: 2 }),
+        "9"
+    );
+
+    let row_col = Renderer {
+        style: Style::Unicode,
+        scheme: Some(Scheme::RowCol),
+        ..phone
+    };
+    assert!(row_col.render(state.board()).contains("│ X │1,2│1,3│"));
+}
@@ -0,0 +1,66 @@
+use ppttt::game::{GameResult, Piece};
+use ppttt::series::{Format, Series, Tally};
+
+#[test]
+fn players_swap_sides_every_game() {
+    let mut series = Series::new(Format::BestOf(5), ["Player", "Computer"], Piece::O);
+    let mut sides = Vec::new();
+    for _ in 0..4 {
+        let side = series.next_side();
+        sides.push(side);
+        series.record(side, GameResult::Draw);
+    }
+    assert_eq!(sides, [Piece::O, Piece::X, Piece::O, Piece::X]);
+}
+
+#[test]
+fn best_of_stops_once_the_match_is_decided() {
+    let mut series = Series::new(Format::BestOf(3), ["Player", "Computer"], Piece::X);
+    // player 0 wins as X, then again as O
+    series.record(Piece::X, GameResult::XWins);
+    assert!(!series.is_over());
+    series.record(Piece::O, GameResult::OWins);
+    assert!(series.is_over());
+    assert_eq!(series.leader(), Some(0));
+    assert_eq!(series.summary(), "Player wins the match 2-0 with 0 draws");
+
+    let mut drawn = Series::new(Format::BestOf(2), ["A", "B"], Piece::X);
+    drawn.record(Piece::X, GameResult::Draw);
+    drawn.record(Piece::O, GameResult::Draw);
+    assert!(drawn.is_over());
+    assert_eq!(drawn.leader(), None);
+    assert_eq!(drawn.summary(), "The match is tied 0-0 with 2 draws");
+}
+
+#[test]
+fn first_to_ignores_draws() {
+    let mut series = Series::new(Format::FirstTo(2), ["A", "B"], Piece::X);
+    for _ in 0..6 {
+        series.record(series.next_side(), GameResult::Draw);
+    }
+    assert!(!series.is_over());
+
+    // player 0 plays X in the next game and loses, then O and loses again
+    series.record(series.next_side(), GameResult::OWins);
+    series.record(series.next_side(), GameResult::XWins);
+    assert!(series.is_over());
+    assert_eq!(series.leader(), Some(1));
+    assert_eq!(
+        series.tally(1),
+        Tally {
+            wins: 2,
+            losses: 0,
+            draws: 6
+        }
+    );
+    assert_eq!(
+        series.tally_as(1, Piece::O),
+        Tally {
+            wins: 1,
+            losses: 0,
+            draws: 3
+        }
+    );
+    assert_eq!(series.tally_as(0, Piece::X).losses, 1);
+    assert!(series.to_string().contains("  as O"));
+}
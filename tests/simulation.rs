@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+
+use ppttt::ai::{
+    self,
+    mcts::{Budget, DEFAULT_EXPLORATION},
+    Selection,
+};
+use ppttt::game::{move_code_to_position, Board, GameResult, GameState, Piece, Position};
+use ppttt::player::{run_game, Action, ComputerPlayer, Player, ScriptedPlayer};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    Random,
+    Minimax,
+}
+
+#[test]
+fn never_lose_vs_random() {
+    const NUM_GAMES: u64 = 100;
+
+    for seed in 0..NUM_GAMES {
+        let winner = run_game(
+            GameState::new(Board::new()),
+            &mut ComputerPlayer::minimax().with_seed(seed),
+            &mut ComputerPlayer::random().with_seed(seed),
+            None,
+        )
+        .unwrap()
+        .result()
+        .winner();
+        assert_ne!(winner, Some(Piece::O), "random won with seed {}", seed);
+    }
+}
+
+#[test]
+fn many_games() {
+    const NUM_GAMES: u64 = 10000;
+
+    let results: Vec<Option<Strategy>> = (0..NUM_GAMES)
+        .into_par_iter()
+        .progress_count(NUM_GAMES)
+        .map(do_mm_vs_random_game)
+        .collect();
+
+    let wins_for_random = results
+        .iter()
+        .filter(|&&result| result == Some(Strategy::Random))
+        .count();
+    assert_eq!(wins_for_random, 0);
+}
+
+pub fn do_mm_vs_random_game(seed: u64) -> Option<Strategy> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let first = *[Strategy::Random, Strategy::Minimax]
+        .choose(&mut rng)
+        .unwrap();
+
+    let mut minimax = ComputerPlayer::minimax().with_seed(rng.gen());
+    let mut random = ComputerPlayer::random().with_seed(rng.gen());
+    let state = GameState::new(Board::new());
+    let winner = match first {
+        Strategy::Minimax => run_game(state, &mut minimax, &mut random, None).unwrap(),
+        Strategy::Random => run_game(state, &mut random, &mut minimax, None).unwrap(),
+    }
+    .result()
+    .winner();
+
+    // whoever went first played X
+    winner.map(|piece| match (piece, first) {
+        (Piece::X, first) => first,
+        (Piece::O, Strategy::Minimax) => Strategy::Random,
+        (Piece::O, Strategy::Random) => Strategy::Minimax,
+    })
+}
+
+#[test]
+fn scripted_players_replay_a_game() {
+    // X takes the top row while O wanders
+    let mut x = ScriptedPlayer::new([
+        Position { x: 0, y: 0 },
+        Position { x: 1, y: 0 },
+        Position { x: 2, y: 0 },
+    ]);
+    let mut o = ScriptedPlayer::new([Position { x: 0, y: 1 }, Position { x: 2, y: 2 }]);
+    let game = run_game(GameState::new(Board::new()), &mut x, &mut o, None).unwrap();
+    assert_eq!(game.result(), GameResult::XWins);
+}
+
+/// Stands in for a person at the keyboard.
+struct ScriptedHuman {
+    actions: VecDeque<Action>,
+}
+
+impl Player for ScriptedHuman {
+    fn name(&self, piece: Piece) -> String {
+        format!("Human {}", piece)
+    }
+
+    fn choose_move(&mut self, _state: &GameState) -> Position {
+        unreachable!("the runner asks for actions")
+    }
+
+    fn choose_action(&mut self, _state: &GameState) -> Action {
+        self.actions.pop_front().expect("Human ran out of actions")
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn undo_takes_back_the_computers_reply_too() {
+    let code = |c| move_code_to_position(c).unwrap();
+    let mut human = ScriptedHuman {
+        actions: VecDeque::from([
+            Action::Play(code("5")),
+            Action::Undo,
+            Action::Redo,
+            Action::Play(code("9")),
+            Action::Undo,
+            Action::Undo,
+            Action::Undo,
+            Action::Play(code("1")),
+            Action::Play(code("9")),
+            Action::Play(code("5")),
+        ]),
+    };
+    // redone moves are replayed, so the computer only picks again after 9 and after the restart
+    let mut computer = ScriptedPlayer::new(["7", "3", "7", "3"].map(code));
+
+    let game = run_game(
+        GameState::new(Board::new()),
+        &mut human,
+        &mut computer,
+        None,
+    )
+    .unwrap();
+    assert_eq!(game.result(), GameResult::XWins);
+    assert_eq!(game.moves(), ["1", "7", "9", "3", "5"].map(code));
+}
+
+#[test]
+fn seeded_computers_repeat_their_games() {
+    let play = |seed: u64| {
+        let mut x = ComputerPlayer::random().with_seed(seed);
+        let mut o = ComputerPlayer::new(
+            ai::Strategy::Mcts {
+                budget: Budget::Iterations(200),
+                exploration: DEFAULT_EXPLORATION,
+                trees: 1,
+            },
+            Selection::Best,
+        )
+        .with_seed(seed + 1);
+        run_game(
+            GameState::with_first(Board::new(), Piece::O),
+            &mut x,
+            &mut o,
+            None,
+        )
+        .unwrap()
+    };
+
+    let game = play(7);
+    assert_eq!(game.start().to_move(), Piece::O);
+    for _ in 0..5 {
+        assert_eq!(play(7).moves(), game.moves());
+    }
+}
+
+#[test]
+fn runner_asks_people_again_after_illegal_moves() {
+    // O's first try is on X's piece, so the runner asks again
+    let mut x = ScriptedPlayer::new([
+        Position { x: 1, y: 1 },
+        Position { x: 0, y: 0 },
+        Position { x: 2, y: 2 },
+    ]);
+    let mut o = ScriptedHuman {
+        actions: VecDeque::from([
+            Action::Play(Position { x: 1, y: 1 }),
+            Action::Play(Position { x: 0, y: 1 }),
+            Action::Play(Position { x: 0, y: 2 }),
+        ]),
+    };
+    let game = run_game(GameState::new(Board::new()), &mut x, &mut o, None).unwrap();
+    assert_eq!(game.result(), GameResult::XWins);
+}
+
+#[test]
+fn computers_forfeit_illegal_moves() {
+    // asking again would only get the same move, forever
+    let mut x = ScriptedPlayer::new([Position { x: 1, y: 1 }, Position { x: 1, y: 1 }]);
+    let mut o = ScriptedPlayer::new([Position { x: 0, y: 0 }]);
+    let forfeit = run_game(GameState::new(Board::new()), &mut x, &mut o, None).unwrap_err();
+    assert_eq!(forfeit.piece, Piece::X);
+    assert_eq!(forfeit.result(), GameResult::OWins);
+    assert_eq!(forfeit.game.moves().len(), 2);
+    assert!(
+        forfeit.reason.contains("illegal move 5"),
+        "{}",
+        forfeit.reason
+    );
+
+    // cells off the board have no name to give in the reason
+    for (board, pos) in [
+        (Board::new(), Position { x: 9, y: 9 }),
+        (
+            Board::with_size(4, 4, 3).unwrap(),
+            Position { x: 200, y: 0 },
+        ),
+    ] {
+        let mut x = ScriptedPlayer::new([pos]);
+        let mut o = ScriptedPlayer::new([]);
+        let forfeit = run_game(GameState::new(board), &mut x, &mut o, None).unwrap_err();
+        assert_eq!(forfeit.piece, Piece::X);
+        assert!(
+            forfeit.reason.contains(&format!("({}, {})", pos.x, pos.y)),
+            "{}",
+            forfeit.reason
+        );
+    }
+}
+
+#[test]
+fn minimax_beats_a_script_that_ignores_threats() {
+    let mut script = ScriptedPlayer::new([
+        Position { x: 0, y: 0 },
+        Position { x: 0, y: 2 },
+        Position { x: 2, y: 0 },
+        Position { x: 2, y: 2 },
+    ]);
+    let mut minimax = ComputerPlayer::minimax();
+    let game = run_game(
+        GameState::new(Board::new()),
+        &mut script,
+        &mut minimax,
+        None,
+    )
+    .unwrap();
+    assert_eq!(game.result(), GameResult::OWins);
+}
+
+#[test]
+fn mcts_holds_its_own_against_minimax() {
+    const NUM_GAMES: u64 = 20;
+    let mcts = ai::Strategy::Mcts {
+        budget: Budget::Iterations(5000),
+        exploration: DEFAULT_EXPLORATION,
+        trees: 1,
+    };
+
+    let mut mcts_losses = 0;
+    for game in 0..NUM_GAMES {
+        let mut mcts_player = ComputerPlayer::new(mcts, Selection::Best).with_seed(game);
+        let mut minimax = ComputerPlayer::minimax().with_seed(game);
+        let (x, o): (&mut dyn Player, &mut dyn Player) = if game % 2 == 0 {
+            (&mut mcts_player, &mut minimax)
+        } else {
+            (&mut minimax, &mut mcts_player)
+        };
+        let winner = run_game(GameState::new(Board::new()), x, o, None)
+            .unwrap()
+            .result()
+            .winner();
+        let mcts_piece = if game % 2 == 0 { Piece::X } else { Piece::O };
+
+        // minimax never loses; mcts should mostly hold the draw
+        assert_ne!(winner, Some(mcts_piece));
+        if winner.is_some() {
+            mcts_losses += 1;
+        }
+    }
+    assert!(
+        mcts_losses <= NUM_GAMES / 4,
+        "MCTS lost {} of {} games to minimax",
+        mcts_losses,
+        NUM_GAMES
+    );
+}
+
+#[test]
+fn mcts_beats_random() {
+    const NUM_GAMES: u64 = 20;
+    let mcts = ai::Strategy::Mcts {
+        budget: Budget::Iterations(2000),
+        exploration: DEFAULT_EXPLORATION,
+        trees: 1,
+    };
+
+    let mut wins = 0;
+    for seed in 0..NUM_GAMES {
+        let winner = run_game(
+            GameState::new(Board::new()),
+            &mut ComputerPlayer::new(mcts, Selection::Best).with_seed(seed),
+            &mut ComputerPlayer::random().with_seed(seed),
+            None,
+        )
+        .unwrap()
+        .result()
+        .winner();
+        assert_ne!(winner, Some(Piece::O));
+        if winner == Some(Piece::X) {
+            wins += 1;
+        }
+    }
+    assert!(wins >= NUM_GAMES / 2);
+}
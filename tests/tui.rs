@@ -0,0 +1,66 @@
+use ratatui::{backend::TestBackend, Terminal};
+
+use ppttt::game::{Board, GameState, Position};
+use ppttt::tui::View;
+
+fn draw(view: &mut View, state: &GameState) -> Vec<String> {
+    let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+    terminal
+        .draw(|frame| view.render(frame, state, "status"))
+        .unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn clicks_land_on_the_cell_drawn_there() {
+    let state: GameState = "X../.O./... x".parse().unwrap();
+    let mut view = View::new(&state);
+    let screen = draw(&mut view, &state);
+
+    // find the X and the O on screen and click them
+    let find = |piece: char| {
+        screen
+            .iter()
+            .enumerate()
+            .find_map(|(row, line)| {
+                line.chars()
+                    .position(|c| c == piece)
+                    .map(|column| (column as u16, row as u16))
+            })
+            .unwrap()
+    };
+    let (column, row) = find('X');
+    assert_eq!(
+        view.cell_at(&state, column, row),
+        Some(Position { x: 0, y: 0 })
+    );
+    let (column, row) = find('O');
+    assert_eq!(
+        view.cell_at(&state, column, row),
+        Some(Position { x: 1, y: 1 })
+    );
+    // the border left of the X is no cell
+    let (column, row) = find('X');
+    assert_eq!(view.cell_at(&state, column - 2, row), None);
+
+    assert!(screen.iter().any(|line| line.contains("Moves")));
+    assert_eq!(screen.last().unwrap().trim_end(), "status");
+}
+
+#[test]
+fn the_cursor_stays_on_the_board() {
+    let state = GameState::new(Board::with_size(4, 3, 3).unwrap());
+    let mut view = View::new(&state);
+    assert_eq!(view.cursor, Position { x: 2, y: 1 });
+    view.move_cursor(&state, 5, 0);
+    assert_eq!(view.cursor, Position { x: 3, y: 1 });
+    view.move_cursor(&state, -1, -4);
+    assert_eq!(view.cursor, Position { x: 2, y: 0 });
+}